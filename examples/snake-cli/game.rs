@@ -88,7 +88,12 @@ impl Game {
         }
     }
 
-    fn game_over(&self) -> bool {
+    /// Sizes of the snakes still alive
+    pub fn sizes(&self) -> Vec<usize> {
+        self.snakes.iter().map(Snake::size).collect()
+    }
+
+    pub fn game_over(&self) -> bool {
         // When the snakes don't eat obviously starve
         self.snakes.is_empty() || self.ticks_since_eaten >= 150
     }
@@ -1,5 +1,9 @@
 use defs::{OUTPUTS, SNAKE_COUNT};
+use neat_gru::benchmarks::Harness;
+use neat_gru::topology::mutation_probabilities::MutationProbabilities;
+use neat_gru::train::config::TrainConfig;
 use neat_gru::train::Train;
+use snake_benchmark::SnakeBenchmark;
 use training_simulation::TrainingSimulation;
 
 mod apple;
@@ -9,11 +13,39 @@ mod direction;
 pub mod error;
 mod game;
 mod snake;
+mod snake_benchmark;
 mod training_simulation;
 mod utils;
 
 fn main() {
-    run_training();
+    // `cargo run --release --example snake-cli -- --benchmark`
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        run_benchmarks();
+    } else {
+        run_training();
+    }
+}
+
+/// Compares two mutation rates on the built-in benchmarks and the snake game, writes every run
+/// to benchmarks.csv and prints a summary
+fn run_benchmarks() {
+    let default = TrainConfig {
+        iterations: 100,
+        ..Default::default()
+    };
+    let topology_mutations = TrainConfig {
+        mutation_probabilities: MutationProbabilities::new(0.8, 0.2).unwrap(),
+        ..default.clone()
+    };
+    let results = Harness::new()
+        .configuration("default", default)
+        .configuration("topology mutations", topology_mutations)
+        .standard_benchmarks()
+        .benchmark(|_| SnakeBenchmark)
+        .run()
+        .unwrap();
+    std::fs::write("benchmarks.csv", results.to_csv()).expect("Could not write benchmarks.csv");
+    println!("{}", results.to_markdown());
 }
 
 fn run_training() {
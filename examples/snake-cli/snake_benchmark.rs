@@ -0,0 +1,43 @@
+use crate::defs::OUTPUTS;
+use crate::game::Game;
+use neat_gru::benchmarks::Benchmark;
+use neat_gru::evaluation::evaluator::Evaluator;
+use neat_gru::neural_network::NeuralNetwork;
+
+/// The snake game played by one snake alone, for the benchmark harness
+///
+/// The fitness is the size of the snake at game over, whether it collided or starved. The apples
+/// are placed randomly, so the fitness of a network changes from one game to the other
+pub struct SnakeBenchmark;
+
+impl Evaluator<f64> for SnakeBenchmark {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let mut game = Game::new(vec![net.clone()]);
+        // A colliding snake is removed during its last tick, with the size it had before it
+        let mut size = 0;
+        while !game.game_over() {
+            size = game.sizes()[0];
+            game.tick();
+        }
+        game.sizes().first().copied().unwrap_or(size) as f64
+    }
+}
+
+impl Benchmark for SnakeBenchmark {
+    fn name(&self) -> String {
+        "snake".to_string()
+    }
+
+    fn inputs(&self) -> usize {
+        4
+    }
+
+    fn outputs(&self) -> usize {
+        OUTPUTS
+    }
+
+    /// A snake of 10 blocks
+    fn solved_threshold(&self) -> f64 {
+        10.0
+    }
+}
@@ -0,0 +1,66 @@
+use crate::benchmarks::{decision_fitness, Benchmark};
+use crate::evaluation::evaluator::Evaluator;
+use crate::neural_network::NeuralNetwork;
+
+/// Outputs whether an odd number of the input bits are set, for every combination of bits. The
+/// state of the network is reset before every combination
+///
+/// The fitness is the number of correct answers plus the mean closeness to the answers, solved
+/// once every answer is correct
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parity {
+    bits: usize,
+}
+
+impl Parity {
+    /// # Arguments
+    ///
+    /// `bits` - The number of inputs, at least 1
+    pub fn new(bits: usize) -> Parity {
+        Parity { bits: bits.max(1) }
+    }
+
+    /// Parity of 2 bits
+    pub fn xor() -> Parity {
+        Parity::new(2)
+    }
+
+    fn cases(&self) -> usize {
+        1 << self.bits
+    }
+}
+
+impl Evaluator<f64> for Parity {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let decisions = (0..self.cases()).map(|case: usize| {
+            let inputs: Vec<f64> = (0..self.bits)
+                .map(|bit| ((case >> bit) & 1) as f64)
+                .collect();
+            net.reset_state();
+            (net.compute(&inputs)[0], case.count_ones() % 2 == 1)
+        });
+        decision_fitness(decisions)
+    }
+}
+
+impl Benchmark for Parity {
+    fn name(&self) -> String {
+        if self.bits == 2 {
+            "xor".to_string()
+        } else {
+            format!("parity-{}", self.bits)
+        }
+    }
+
+    fn inputs(&self) -> usize {
+        self.bits
+    }
+
+    fn outputs(&self) -> usize {
+        1
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        self.cases() as f64
+    }
+}
@@ -0,0 +1,412 @@
+//! Runs training configurations against benchmarks for several seeds and summarizes the results
+use crate::benchmarks::{standard_with_seed, Benchmark};
+use crate::game::{Game, NetworkInfo};
+#[cfg(target_arch = "wasm32")]
+use crate::instant_wasm_replacement::Instant;
+use crate::neural_network::NeuralNetwork;
+use crate::train::config::TrainConfig;
+use crate::train::error::TrainingError;
+use crate::train::{HistoricTopologyLazy, Train};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+type BenchmarkFactory = Box<dyn Fn(u64) -> Box<dyn Benchmark>>;
+
+/// One training of a configuration on a benchmark
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HarnessRun {
+    pub configuration: String,
+    pub benchmark: String,
+    pub seed: u64,
+    /// Number of generations run until a network solved the benchmark, None if it wasn't solved
+    pub generations_to_solve: Option<usize>,
+    /// Number of generations run
+    pub generations: usize,
+    /// Best fitness of the training
+    pub final_fitness: f64,
+    /// Number of genes of the network with the best fitness
+    pub genome_size: usize,
+    /// Duration of the training in seconds
+    pub wall_time: f64,
+}
+
+/// Mean and sample standard deviation of some values
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Statistics {
+    pub mean: f64,
+    pub standard_deviation: f64,
+}
+
+impl Statistics {
+    /// Returns None without values
+    pub fn new<I: IntoIterator<Item = f64>>(values: I) -> Option<Statistics> {
+        let values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / count;
+        let variance = if values.len() > 1 {
+            values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / (count - 1.0)
+        } else {
+            0.0
+        };
+        Some(Statistics {
+            mean,
+            standard_deviation: variance.sqrt(),
+        })
+    }
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} ± {:.2}", self.mean, self.standard_deviation)
+    }
+}
+
+/// Runs of a configuration on a benchmark, over every seed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HarnessSummary {
+    pub configuration: String,
+    pub benchmark: String,
+    pub runs: usize,
+    /// Number of runs that solved the benchmark
+    pub solved: usize,
+    /// Over the runs that solved the benchmark, None if none did
+    pub generations_to_solve: Option<Statistics>,
+    pub final_fitness: Statistics,
+    pub genome_size: Statistics,
+    pub wall_time: Statistics,
+}
+
+/// Every run of `Harness::run`, in the order of the configurations, benchmarks and seeds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HarnessResults {
+    pub runs: Vec<HarnessRun>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl HarnessResults {
+    /// Groups the runs by configuration and benchmark, in the order of the runs
+    pub fn summaries(&self) -> Vec<HarnessSummary> {
+        let mut groups: Vec<(&str, &str, Vec<&HarnessRun>)> = Vec::new();
+        for run in &self.runs {
+            let position = groups.iter().position(|(configuration, benchmark, _)| {
+                *configuration == run.configuration && *benchmark == run.benchmark
+            });
+            match position {
+                Some(position) => groups[position].2.push(run),
+                None => groups.push((&run.configuration, &run.benchmark, vec![run])),
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(configuration, benchmark, runs)| {
+                let statistics = |value: fn(&HarnessRun) -> f64| {
+                    Statistics::new(runs.iter().map(|run| value(run))).unwrap()
+                };
+                let solved: Vec<f64> = runs
+                    .iter()
+                    .filter_map(|run| run.generations_to_solve)
+                    .map(|generations| generations as f64)
+                    .collect();
+                HarnessSummary {
+                    configuration: configuration.to_string(),
+                    benchmark: benchmark.to_string(),
+                    runs: runs.len(),
+                    solved: solved.len(),
+                    generations_to_solve: Statistics::new(solved),
+                    final_fitness: statistics(|run| run.final_fitness),
+                    genome_size: statistics(|run| run.genome_size as f64),
+                    wall_time: statistics(|run| run.wall_time),
+                }
+            })
+            .collect()
+    }
+
+    /// One line per run, with a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "configuration,benchmark,seed,generations_to_solve,generations,final_fitness,\
+             genome_size,wall_time\n",
+        );
+        for run in &self.runs {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{}\n",
+                csv_field(&run.configuration),
+                csv_field(&run.benchmark),
+                run.seed,
+                run.generations_to_solve
+                    .map_or(String::new(), |generations| generations.to_string()),
+                run.generations,
+                run.final_fitness,
+                run.genome_size,
+                run.wall_time
+            ));
+        }
+        csv
+    }
+
+    /// A table of the summaries, the statistics are the mean ± the standard deviation
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from(
+            "| Configuration | Benchmark | Solved | Generations to solve | Final fitness \
+             | Genome size | Wall time (s) |\n|---|---|---|---|---|---|---|\n",
+        );
+        for summary in self.summaries() {
+            markdown.push_str(&format!(
+                "| {} | {} | {}/{} | {} | {} | {} | {} |\n",
+                summary.configuration,
+                summary.benchmark,
+                summary.solved,
+                summary.runs,
+                summary
+                    .generations_to_solve
+                    .map_or("-".to_string(), |statistics| statistics.to_string()),
+                summary.final_fitness,
+                summary.genome_size,
+                summary.wall_time
+            ));
+        }
+        markdown
+    }
+}
+
+/// Evaluates the networks on a benchmark, keeping the fitness and the genome size of the best
+/// network
+struct HarnessGame<'b> {
+    benchmark: &'b dyn Benchmark,
+    nets: Vec<NeuralNetwork<f64>>,
+    genome_sizes: Vec<usize>,
+    best: Option<(f64, usize)>,
+}
+
+impl Game<f64> for HarnessGame<'_> {
+    fn run_generation(&mut self) -> Vec<f64> {
+        let benchmark = self.benchmark;
+        let fitness: Vec<f64> = cond_iter_mut!(self.nets)
+            .map(|net| {
+                net.reset_state();
+                benchmark.evaluate(net)
+            })
+            .collect();
+        for (&value, &genome_size) in fitness.iter().zip(&self.genome_sizes) {
+            if value.is_finite() && self.best.is_none_or(|(best, _)| value > best) {
+                self.best = Some((value, genome_size));
+            }
+        }
+        fitness
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.genome_sizes = vec![0; nets.len()];
+        self.nets = nets;
+    }
+
+    fn reset_players_with_info(&mut self, players: Vec<(NetworkInfo, NeuralNetwork<f64>)>) {
+        let (infos, nets): (Vec<NetworkInfo>, Vec<NeuralNetwork<f64>>) =
+            players.into_iter().unzip();
+        self.genome_sizes = infos.iter().map(|info| info.genome_size).collect();
+        self.nets = nets;
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+/// Trains every configuration on every benchmark for every seed
+///
+/// The seeds are repetitions, not reproducible runs: a seed is only given to the benchmarks, e.g.
+/// for the starting angles of the poles, the training itself isn't seeded, so running the same
+/// seed twice gives different results
+///
+/// ```
+/// use neat_gru::benchmarks::{Harness, Parity};
+/// use neat_gru::train::config::TrainConfig;
+///
+/// let quick = TrainConfig {
+///     iterations: 3,
+///     max_individuals: 20,
+///     ..Default::default()
+/// };
+/// let results = Harness::new()
+///     .configuration("quick", quick)
+///     .benchmark(|_| Parity::xor())
+///     .seeds(0..2)
+///     .run()
+///     .unwrap();
+/// println!("{}", results.to_markdown());
+/// ```
+pub struct Harness {
+    configurations: Vec<(String, TrainConfig<f64>)>,
+    benchmarks: Vec<BenchmarkFactory>,
+    seeds: Vec<u64>,
+    stop_when_solved: bool,
+}
+
+impl Harness {
+    /// Default values are:
+    /// - seeds -> 0 to 4
+    /// - stop_when_solved -> true
+    pub fn new() -> Harness {
+        Harness {
+            configurations: Vec::new(),
+            benchmarks: Vec::new(),
+            seeds: (0..5).collect(),
+            stop_when_solved: true,
+        }
+    }
+
+    /// Adds a configuration to compare, its inputs and outputs are replaced by the ones of the
+    /// benchmarks
+    ///
+    /// # Arguments
+    ///
+    /// `name` - Name of the configuration in the results
+    ///
+    /// `config` - The hyperparameters of the training
+    pub fn configuration<N: Into<String>>(mut self, name: N, config: TrainConfig<f64>) -> Self {
+        self.configurations.push((name.into(), config));
+        self
+    }
+
+    /// Adds a benchmark
+    ///
+    /// # Arguments
+    ///
+    /// `factory` - Creates the benchmark of a seed
+    pub fn benchmark<B, G>(mut self, factory: G) -> Self
+    where
+        B: Benchmark + 'static,
+        G: Fn(u64) -> B + 'static,
+    {
+        self.benchmarks
+            .push(Box::new(move |seed| Box::new(factory(seed))));
+        self
+    }
+
+    /// Adds every benchmark of `benchmarks::standard`
+    pub fn standard_benchmarks(mut self) -> Self {
+        for index in 0..standard_with_seed(0).len() {
+            self.benchmarks
+                .push(Box::new(move |seed| standard_with_seed(seed).remove(index)));
+        }
+        self
+    }
+
+    /// Sets the seeds, every configuration is trained once per seed on every benchmark
+    ///
+    /// The seeds only vary the benchmarks, the runs of a seed aren't reproducible
+    ///
+    /// # Arguments
+    ///
+    /// `seeds` - The seeds given to the benchmarks
+    pub fn seeds<I: IntoIterator<Item = u64>>(mut self, seeds: I) -> Self {
+        self.seeds = seeds.into_iter().collect();
+        self
+    }
+
+    /// Sets whether a training stops at the first generation solving the benchmark, otherwise
+    /// every iteration of the configuration is run
+    ///
+    /// # Arguments
+    ///
+    /// `stop` - True to stop once solved
+    pub fn stop_when_solved(mut self, stop: bool) -> Self {
+        self.stop_when_solved = stop;
+        self
+    }
+
+    /// Runs the trainings one after the other
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub fn run(&self) -> Result<HarnessResults, TrainingError> {
+        let mut results = HarnessResults::default();
+        for (name, config) in &self.configurations {
+            for factory in &self.benchmarks {
+                for &seed in &self.seeds {
+                    let benchmark = factory(seed);
+                    let run = self.run_one(name, config, benchmark.as_ref(), seed)?;
+                    log::info!(
+                        "BENCHMARK {} {} seed {}: fitness {} after {} generations",
+                        run.configuration,
+                        run.benchmark,
+                        seed,
+                        run.final_fitness,
+                        run.generations
+                    );
+                    results.runs.push(run);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    fn run_one(
+        &self,
+        name: &str,
+        config: &TrainConfig<f64>,
+        benchmark: &dyn Benchmark,
+        seed: u64,
+    ) -> Result<HarnessRun, TrainingError> {
+        let mut config = config.clone();
+        config.inputs = Some(benchmark.inputs());
+        config.outputs = Some(benchmark.outputs());
+        let mut game = HarnessGame {
+            benchmark,
+            nets: Vec::new(),
+            genome_sizes: Vec::new(),
+            best: None,
+        };
+        let start = Instant::now();
+        let mut generations_to_solve = None;
+        let mut runner = Train::from_config(&config, &mut game);
+        runner.init()?;
+        while runner.generation() < config.iterations {
+            let report = runner.step()?;
+            let solved = runner
+                .simulation
+                .best
+                .is_some_and(|(fitness, _)| benchmark.is_solved(fitness));
+            if solved && generations_to_solve.is_none() {
+                generations_to_solve = Some(report.generation + 1);
+            }
+            if report.extinct || (solved && self.stop_when_solved) {
+                break;
+            }
+        }
+        let generations = runner.generation();
+        runner.finish()?;
+        let (final_fitness, genome_size) = runner.simulation.best.unwrap_or((f64::NAN, 0));
+        Ok(HarnessRun {
+            configuration: name.to_string(),
+            benchmark: benchmark.name(),
+            seed,
+            generations_to_solve,
+            generations,
+            final_fitness,
+            genome_size,
+            wall_time: start.elapsed().as_millis() as f64 / 1000.0,
+        })
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
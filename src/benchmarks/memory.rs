@@ -0,0 +1,176 @@
+use crate::benchmarks::{decision_fitness, Benchmark};
+use crate::evaluation::evaluator::Evaluator;
+use crate::neural_network::NeuralNetwork;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Remembers a bit until a cue: the first input is -1 or 1 at the first step, then 0 until the
+/// second input is 1, `delay` steps later, where the output must have the sign of the bit. Every
+/// delay from 1 to `delay` is tried with both bits, from a reset state
+///
+/// The fitness is the number of correct answers plus the mean closeness to the answers, solved
+/// once every answer is correct
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceRecall {
+    delay: usize,
+}
+
+impl SequenceRecall {
+    /// # Arguments
+    ///
+    /// `delay` - The longest number of steps between the bit and the cue, at least 1
+    pub fn new(delay: usize) -> SequenceRecall {
+        SequenceRecall {
+            delay: delay.max(1),
+        }
+    }
+}
+
+impl Evaluator<f64> for SequenceRecall {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let mut decisions = Vec::with_capacity(2 * self.delay);
+        for delay in 1..=self.delay {
+            for &bit in &[false, true] {
+                net.reset_state();
+                net.compute(&[if bit { 1.0 } else { -1.0 }, 0.0]);
+                for _ in 1..delay {
+                    net.compute(&[0.0, 0.0]);
+                }
+                decisions.push((net.compute(&[0.0, 1.0])[0], bit));
+            }
+        }
+        decision_fitness(decisions)
+    }
+}
+
+impl Benchmark for SequenceRecall {
+    fn name(&self) -> String {
+        format!("sequence-recall-{}", self.delay)
+    }
+
+    fn inputs(&self) -> usize {
+        2
+    }
+
+    fn outputs(&self) -> usize {
+        1
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        (2 * self.delay) as f64
+    }
+}
+
+/// Counts symbols: one of the inputs is 1 at every step, the largest output must be the one of
+/// the symbol seen the most times so far, the first one on ties
+///
+/// The fitness is the fraction of the steps with the right answer, solved from 0.95
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryCount {
+    symbols: usize,
+    length: usize,
+    seed: u64,
+}
+
+impl MemoryCount {
+    /// Default values are:
+    /// - symbols -> 4
+    /// - length -> 100
+    /// - seed -> 0
+    pub fn new() -> MemoryCount {
+        MemoryCount {
+            symbols: 4,
+            length: 100,
+            seed: 0,
+        }
+    }
+
+    /// Sets the number of different symbols, the number of inputs and outputs
+    ///
+    /// # Arguments
+    ///
+    /// `symbols` - Number of symbols, at least 2
+    pub fn symbols(mut self, symbols: usize) -> Self {
+        self.symbols = symbols.max(2);
+        self
+    }
+
+    /// Sets the number of symbols of the sequence
+    ///
+    /// # Arguments
+    ///
+    /// `length` - Number of steps, at least 1
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length.max(1);
+        self
+    }
+
+    /// Sets the seed the sequence is drawn from
+    ///
+    /// # Arguments
+    ///
+    /// `seed` - Seed of the random number generator
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Default for MemoryCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn argmax(values: &[f64]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |(best, max), (index, &value)| {
+            if value > max {
+                (index, value)
+            } else {
+                (best, max)
+            }
+        })
+        .0
+}
+
+impl Evaluator<f64> for MemoryCount {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut counts = vec![0.0; self.symbols];
+        let mut inputs = vec![0.0; self.symbols];
+        let mut correct = 0;
+        net.reset_state();
+        for _ in 0..self.length {
+            let symbol = rng.gen_range(0..self.symbols);
+            counts[symbol] += 1.0;
+            inputs[symbol] = 1.0;
+            let outputs = net.compute(&inputs);
+            inputs[symbol] = 0.0;
+            if argmax(&outputs) == argmax(&counts) {
+                correct += 1;
+            }
+        }
+        correct as f64 / self.length as f64
+    }
+}
+
+impl Benchmark for MemoryCount {
+    fn name(&self) -> String {
+        format!("memory-count-{}", self.symbols)
+    }
+
+    fn inputs(&self) -> usize {
+        self.symbols
+    }
+
+    fn outputs(&self) -> usize {
+        self.symbols
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        0.95
+    }
+}
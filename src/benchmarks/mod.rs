@@ -0,0 +1,104 @@
+//! Standard tasks to compare training configurations and catch regressions
+//!
+//! Every benchmark is an `Evaluator` of `f64` networks, run with an `EvaluatorGame`, and is
+//! deterministic: the random parts of a task are drawn from a fixed seed
+use crate::evaluation::evaluator::{Evaluator, EvaluatorGame};
+use crate::neural_network::NeuralNetwork;
+
+pub mod classification;
+pub mod harness;
+pub mod memory;
+pub mod pole_balancing;
+pub mod t_maze;
+
+pub use classification::Parity;
+pub use harness::Harness;
+pub use memory::{MemoryCount, SequenceRecall};
+pub use pole_balancing::PoleBalancing;
+pub use t_maze::TMaze;
+
+/// A task with a known number of inputs and outputs, solved once a network reaches a fitness
+pub trait Benchmark: Evaluator<f64> {
+    /// Name of the task, e.g. in the results of a benchmark run
+    fn name(&self) -> String;
+
+    /// Number of inputs of the networks
+    fn inputs(&self) -> usize;
+
+    /// Number of outputs of the networks
+    fn outputs(&self) -> usize;
+
+    /// The networks with this fitness or more solve the task
+    fn solved_threshold(&self) -> f64;
+
+    /// Whether a network with this fitness solves the task
+    fn is_solved(&self, fitness: f64) -> bool {
+        fitness >= self.solved_threshold()
+    }
+}
+
+impl Evaluator<f64> for &dyn Benchmark {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        (**self).evaluate(net)
+    }
+}
+
+/// The `Game` running a benchmark
+///
+/// # Arguments
+///
+/// `benchmark` - The task the networks are evaluated on
+pub fn game<B: Benchmark>(benchmark: B) -> EvaluatorGame<f64, B> {
+    EvaluatorGame::new(benchmark)
+}
+
+/// XOR, 3-bit parity, sequence recall, memory count, single and double pole balancing with and
+/// without velocities, and the T-maze, with their default settings
+pub fn standard() -> Vec<Box<dyn Benchmark>> {
+    standard_with_seed(0)
+}
+
+/// Same as `standard`, with the random parts of the tasks drawn from another seed
+///
+/// # Arguments
+///
+/// `seed` - Seed of the memory count sequence and of the starting angles of the poles
+pub fn standard_with_seed(seed: u64) -> Vec<Box<dyn Benchmark>> {
+    vec![
+        Box::new(Parity::xor()),
+        Box::new(Parity::new(3)),
+        Box::new(SequenceRecall::new(5)),
+        Box::new(MemoryCount::new().seed(seed)),
+        Box::new(PoleBalancing::single(true).seed(seed)),
+        Box::new(PoleBalancing::single(false).seed(seed)),
+        Box::new(PoleBalancing::double(true).seed(seed)),
+        Box::new(PoleBalancing::double(false).seed(seed)),
+        Box::new(TMaze::new(10)),
+    ]
+}
+
+/// Fitness of binary decisions: the number of correct decisions, where an output above 0 means
+/// true, plus the mean closeness of the outputs to -1 or 1, in [0, 1]. So the fitness reaches
+/// the number of decisions only when they are all correct
+///
+/// # Arguments
+///
+/// `decisions` - The output of the network and the expected answer of every decision
+pub(crate) fn decision_fitness<I>(decisions: I) -> f64
+where
+    I: IntoIterator<Item = (f64, bool)>,
+{
+    let (mut correct, mut closeness, mut count) = (0.0, 0.0, 0.0);
+    for (output, expected) in decisions {
+        let target = if expected { 1.0 } else { -1.0 };
+        if (output > 0.0) == expected {
+            correct += 1.0;
+        }
+        closeness += 1.0 - (output - target).abs().min(2.0) / 2.0;
+        count += 1.0;
+    }
+    if count == 0.0 {
+        return 0.0;
+    }
+    correct + closeness / count
+}
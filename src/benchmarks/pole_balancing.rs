@@ -0,0 +1,249 @@
+use crate::benchmarks::Benchmark;
+use crate::evaluation::evaluator::Evaluator;
+use crate::neural_network::NeuralNetwork;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const GRAVITY: f64 = -9.8;
+const CART_MASS: f64 = 1.0;
+const FORCE: f64 = 10.0;
+// Friction of the hinges of the poles
+const POLE_FRICTION: f64 = 0.000002;
+const TRACK_LIMIT: f64 = 2.4;
+const TIME_STEP: f64 = 0.01;
+// Integration steps per action
+const STEPS_PER_ACTION: usize = 2;
+
+/// A pole on the cart, hinged at the bottom
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Pole {
+    mass: f64,
+    half_length: f64,
+}
+
+/// Balances one or two poles on a cart moving on a track, the single pole task and the double
+/// pole task of the NEAT paper, with the equations of Wieland integrated with Runge-Kutta
+///
+/// The inputs are the position of the cart and the angles of the poles, normalized to [-1, 1],
+/// followed by their velocities when they are given. The output is the force pushing the cart,
+/// from -10N to 10N, every 0.02s. An episode fails once the cart leaves the track or a pole
+/// falls further than the failure angle, 12 degrees with one pole and 36 degrees with two
+///
+/// The fitness is the mean fraction of the steps balanced in every episode, solved at 1. The
+/// first pole of every episode starts with a random angle within 4 degrees, from a fixed seed
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoleBalancing {
+    poles: Vec<Pole>,
+    velocities: bool,
+    failure_angle: f64,
+    max_steps: usize,
+    episodes: usize,
+    seed: u64,
+}
+
+impl PoleBalancing {
+    fn new(poles: Vec<Pole>, velocities: bool, failure_angle_degrees: f64) -> PoleBalancing {
+        PoleBalancing {
+            poles,
+            velocities,
+            failure_angle: failure_angle_degrees.to_radians(),
+            max_steps: 1000,
+            episodes: 3,
+            seed: 0,
+        }
+    }
+
+    /// One pole of 1m, default values are:
+    /// - max_steps -> 1000
+    /// - episodes -> 3
+    /// - seed -> 0
+    ///
+    /// # Arguments
+    ///
+    /// `velocities` - Whether the velocities are inputs, without them the network must
+    /// estimate them from the previous positions
+    pub fn single(velocities: bool) -> PoleBalancing {
+        let pole = Pole {
+            mass: 0.1,
+            half_length: 0.5,
+        };
+        PoleBalancing::new(vec![pole], velocities, 12.0)
+    }
+
+    /// Two poles of 1m and 0.1m, same default values as `single`
+    ///
+    /// # Arguments
+    ///
+    /// `velocities` - Whether the velocities are inputs, without them the network must
+    /// estimate them from the previous positions
+    pub fn double(velocities: bool) -> PoleBalancing {
+        let long = Pole {
+            mass: 0.1,
+            half_length: 0.5,
+        };
+        let short = Pole {
+            mass: 0.01,
+            half_length: 0.05,
+        };
+        PoleBalancing::new(vec![long, short], velocities, 36.0)
+    }
+
+    /// Sets the number of steps of a successful episode
+    ///
+    /// # Arguments
+    ///
+    /// `max_steps` - Number of actions, at least 1
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Sets the number of episodes, each from its own starting angle
+    ///
+    /// # Arguments
+    ///
+    /// `episodes` - Number of episodes, at least 1
+    pub fn episodes(mut self, episodes: usize) -> Self {
+        self.episodes = episodes.max(1);
+        self
+    }
+
+    /// Sets the seed the starting angles are drawn from
+    ///
+    /// # Arguments
+    ///
+    /// `seed` - Seed of the random number generator
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Derivative of the state, `[x, x', angle, angle', ...]`, when the cart is pushed by
+    /// `force`
+    fn derivatives(&self, state: &[f64], force: f64) -> Vec<f64> {
+        let mut forces = force;
+        let mut masses = CART_MASS;
+        for (pole, values) in self.poles.iter().zip(state[2..].chunks(2)) {
+            let (angle, angular_velocity) = (values[0], values[1]);
+            let mass_length = pole.mass * pole.half_length;
+            let friction = POLE_FRICTION * angular_velocity / mass_length;
+            forces += mass_length * angular_velocity * angular_velocity * angle.sin()
+                + 0.75 * pole.mass * angle.cos() * (friction + GRAVITY * angle.sin());
+            masses += pole.mass * (1.0 - 0.75 * angle.cos() * angle.cos());
+        }
+        let acceleration = forces / masses;
+        let mut derivatives = vec![state[1], acceleration];
+        for (pole, values) in self.poles.iter().zip(state[2..].chunks(2)) {
+            let (angle, angular_velocity) = (values[0], values[1]);
+            let friction = POLE_FRICTION * angular_velocity / (pole.mass * pole.half_length);
+            let angular_acceleration = -0.75
+                * (acceleration * angle.cos() + GRAVITY * angle.sin() + friction)
+                / pole.half_length;
+            derivatives.extend_from_slice(&[angular_velocity, angular_acceleration]);
+        }
+        derivatives
+    }
+
+    /// Fourth order Runge-Kutta step
+    fn integrate(&self, state: &mut [f64], force: f64) {
+        let shifted = |state: &[f64], derivatives: &[f64], factor: f64| -> Vec<f64> {
+            state
+                .iter()
+                .zip(derivatives)
+                .map(|(value, derivative)| value + factor * derivative)
+                .collect()
+        };
+        let k1 = self.derivatives(state, force);
+        let k2 = self.derivatives(&shifted(state, &k1, TIME_STEP / 2.0), force);
+        let k3 = self.derivatives(&shifted(state, &k2, TIME_STEP / 2.0), force);
+        let k4 = self.derivatives(&shifted(state, &k3, TIME_STEP), force);
+        for (index, value) in state.iter_mut().enumerate() {
+            *value += TIME_STEP / 6.0 * (k1[index] + 2.0 * k2[index] + 2.0 * k3[index] + k4[index]);
+        }
+    }
+
+    fn network_inputs(&self, state: &[f64]) -> Vec<f64> {
+        let mut inputs = vec![state[0] / TRACK_LIMIT];
+        inputs.extend(
+            state[2..]
+                .iter()
+                .step_by(2)
+                .map(|angle| angle / self.failure_angle),
+        );
+        if self.velocities {
+            inputs.push(state[1] / 10.0);
+            inputs.extend(state[3..].iter().step_by(2).map(|velocity| velocity / 5.0));
+        }
+        inputs
+    }
+
+    fn failed(&self, state: &[f64]) -> bool {
+        state[0].abs() > TRACK_LIMIT
+            || state[2..]
+                .iter()
+                .step_by(2)
+                .any(|angle| angle.abs() > self.failure_angle)
+    }
+
+    /// Number of steps balanced from the starting state
+    fn run_episode(&self, net: &mut NeuralNetwork<f64>, mut state: Vec<f64>) -> usize {
+        net.reset_state();
+        for step in 0..self.max_steps {
+            let output = net.compute(&self.network_inputs(&state))[0];
+            let force = FORCE * output.clamp(-1.0, 1.0);
+            for _ in 0..STEPS_PER_ACTION {
+                self.integrate(&mut state, force);
+            }
+            if self.failed(&state) || state.iter().any(|value| !value.is_finite()) {
+                return step;
+            }
+        }
+        self.max_steps
+    }
+}
+
+impl Evaluator<f64> for PoleBalancing {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let balanced: usize = (0..self.episodes)
+            .map(|_| {
+                let mut state = vec![0.0; 2 + 2 * self.poles.len()];
+                state[2] = rng.gen_range(-4f64..4.0).to_radians();
+                self.run_episode(net, state)
+            })
+            .sum();
+        balanced as f64 / (self.max_steps * self.episodes) as f64
+    }
+}
+
+impl Benchmark for PoleBalancing {
+    fn name(&self) -> String {
+        let poles = if self.poles.len() == 1 {
+            "cart-pole"
+        } else {
+            "double-pole"
+        };
+        if self.velocities {
+            poles.to_string()
+        } else {
+            format!("{}-no-velocities", poles)
+        }
+    }
+
+    fn inputs(&self) -> usize {
+        let positions = 1 + self.poles.len();
+        if self.velocities {
+            2 * positions
+        } else {
+            positions
+        }
+    }
+
+    fn outputs(&self) -> usize {
+        1
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        1.0
+    }
+}
@@ -0,0 +1,64 @@
+use crate::benchmarks::{decision_fitness, Benchmark};
+use crate::evaluation::evaluator::Evaluator;
+use crate::neural_network::NeuralNetwork;
+
+/// The T-maze of Bakker: the agent walks down a corridor and turns left or right at the
+/// junction, towards the goal shown by a signal at the start of the corridor
+///
+/// The inputs are the signal, -1 for left and 1 for right, only at the first step, whether the
+/// agent is in the corridor, and whether it reached the junction. The agent moves by itself,
+/// the output at the junction is the turn, negative for left. Every corridor length from 1 to
+/// `length` is tried with both goals, from a reset state
+///
+/// The fitness is the number of correct turns plus the mean closeness to the right turns,
+/// solved once every turn is correct
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TMaze {
+    length: usize,
+}
+
+impl TMaze {
+    /// # Arguments
+    ///
+    /// `length` - The number of steps of the longest corridor, at least 1
+    pub fn new(length: usize) -> TMaze {
+        TMaze {
+            length: length.max(1),
+        }
+    }
+}
+
+impl Evaluator<f64> for TMaze {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        let mut decisions = Vec::with_capacity(2 * self.length);
+        for length in 1..=self.length {
+            for &right in &[false, true] {
+                net.reset_state();
+                net.compute(&[if right { 1.0 } else { -1.0 }, 1.0, 0.0]);
+                for _ in 1..length {
+                    net.compute(&[0.0, 1.0, 0.0]);
+                }
+                decisions.push((net.compute(&[0.0, 0.0, 1.0])[0], right));
+            }
+        }
+        decision_fitness(decisions)
+    }
+}
+
+impl Benchmark for TMaze {
+    fn name(&self) -> String {
+        format!("t-maze-{}", self.length)
+    }
+
+    fn inputs(&self) -> usize {
+        3
+    }
+
+    fn outputs(&self) -> usize {
+        1
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        (2 * self.length) as f64
+    }
+}
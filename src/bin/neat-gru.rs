@@ -0,0 +1,84 @@
+//! Trains networks evaluated by an external executable
+//!
+//! Usage: `neat-gru --config <train.toml|train.json> [--output <dir>] -- <evaluator> [args...]`
+//!
+//! See `neat_gru::evaluation::external` for the protocol spoken with the evaluator
+use neat_gru::evaluation::external::ExternalEvaluator;
+use neat_gru::train::config::TrainConfig;
+use neat_gru::train::{Train, TrainingStatus};
+use std::error::Error;
+use std::path::PathBuf;
+use std::process::{self, Command};
+
+const USAGE: &str =
+    "Usage: neat-gru --config <train.toml|train.json> [--output <dir>] -- <evaluator> [args...]";
+
+struct Arguments {
+    config: PathBuf,
+    output: PathBuf,
+    evaluator: Vec<String>,
+}
+
+fn parse_arguments() -> Result<Arguments, String> {
+    let mut args = std::env::args().skip(1);
+    let mut config = None;
+    let mut output = PathBuf::from("neat-gru-output");
+    let mut evaluator = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config = args.next().map(PathBuf::from),
+            "--output" => output = args.next().map(PathBuf::from).ok_or(USAGE)?,
+            "--" => evaluator = args.by_ref().collect(),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            other => return Err(format!("Unknown argument {}\n{}", other, USAGE)),
+        }
+    }
+    if evaluator.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Arguments {
+        config: config.ok_or(USAGE)?,
+        output,
+        evaluator,
+    })
+}
+
+fn run(arguments: Arguments) -> Result<(), Box<dyn Error>> {
+    let config: TrainConfig<f64> = TrainConfig::load(&arguments.config)?;
+    config.validate()?;
+    let mut command = Command::new(&arguments.evaluator[0]);
+    command.args(&arguments.evaluator[1..]);
+    let mut evaluator = ExternalEvaluator::spawn(&mut command)?.output_dir(&arguments.output)?;
+    config.save(arguments.output.join("config.json"))?;
+    let mut runner = Train::from_config(&config, &mut evaluator);
+    let outcome = runner.start()?;
+    let status = match outcome.status {
+        TrainingStatus::Completed => "completed",
+        TrainingStatus::Cancelled => "cancelled",
+        TrainingStatus::Extinct => "extinct",
+    };
+    println!(
+        "Training {} after {} generations, best fitness: {}",
+        status,
+        outcome.generations,
+        runner
+            .simulation
+            .best_fitness()
+            .map_or("none".to_string(), |fitness| fitness.to_string())
+    );
+    Ok(())
+}
+
+fn main() {
+    let arguments = match parse_arguments() {
+        Ok(arguments) => arguments,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            process::exit(2);
+        }
+    };
+    if let Err(err) = run(arguments) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,623 @@
+//! Evaluation of the networks by matches against each other, in one population or in two
+//! co-evolving populations
+use crate::evaluation::evaluator::Aggregation;
+use crate::game::Game;
+use crate::neural_network::NeuralNetwork;
+use crate::train::config::TrainConfig;
+use crate::train::error::{ConfigurationError, TrainingError};
+use crate::train::hall_of_fame::HallOfFameMember;
+use crate::train::{HistoricTopologyLazy, Train, TrainingOutcome};
+use num::Float;
+use rand::seq::SliceRandom;
+use rand::Rng;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use std::borrow::BorrowMut;
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::iter::Sum;
+
+/// Trait to implement for two-player games
+pub trait CompetitiveGame<T>: Sync
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Plays one match, returns the scores of `a` and `b`
+    ///
+    /// # Arguments
+    ///
+    /// `a` - The first player, its state is reset before the match
+    ///
+    /// `b` - The second player, its state is reset before the match
+    fn play(&self, a: &mut NeuralNetwork<T>, b: &mut NeuralNetwork<T>) -> (T, T);
+
+    /// Function to be run at the end of the training
+    ///
+    /// # Arguments
+    ///
+    /// `history` - The best topologies of every generation
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<T>>) {}
+}
+
+/// Chooses the opponents of every network each generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairingSchedule {
+    /// Every network plays against every other network
+    RoundRobin,
+    /// Every network plays against `k` random opponents
+    RandomOpponents(usize),
+    /// Networks with close scores play against each other, over several rounds
+    Swiss { rounds: usize },
+    /// Every network plays against `k` random members of the hall of fame of the training, see
+    /// `Train::hall_of_fame`. Falls back to random opponents while the hall of fame is empty
+    HallOfFame(usize),
+}
+
+impl Default for PairingSchedule {
+    fn default() -> Self {
+        PairingSchedule::RandomOpponents(5)
+    }
+}
+
+/// Schedule and score aggregation shared by both evaluation modes
+struct Tournament<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    schedule: PairingSchedule,
+    aggregation: Aggregation,
+    _float: std::marker::PhantomData<T>,
+}
+
+/// Opponents of a population, either in the same population or in the other one
+enum Opponents<'a, T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    Same,
+    Other(&'a [NeuralNetwork<T>]),
+}
+
+impl<T> Tournament<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+{
+    fn new() -> Tournament<T> {
+        Tournament {
+            schedule: PairingSchedule::default(),
+            aggregation: Aggregation::Mean,
+            _float: std::marker::PhantomData,
+        }
+    }
+
+    /// Plays the matches in parallel, each on copies of the networks
+    fn play_matches<G: CompetitiveGame<T>>(
+        game: &G,
+        first: &[NeuralNetwork<T>],
+        second: &[NeuralNetwork<T>],
+        pairs: &[(usize, usize)],
+    ) -> Vec<(T, T)> {
+        cond_iter!(pairs)
+            .map(|&(a, b)| {
+                let (mut a, mut b) = (first[a].clone(), second[b].clone());
+                a.reset_state();
+                b.reset_state();
+                game.play(&mut a, &mut b)
+            })
+            .collect()
+    }
+
+    /// Returns `k` random indices in `0..len`, never `excluded`
+    fn random_opponents(len: usize, k: usize, excluded: Option<usize>) -> Vec<usize> {
+        let candidates: Vec<usize> = (0..len).filter(|&index| Some(index) != excluded).collect();
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let mut rng = rand::thread_rng();
+        (0..k)
+            .map(|_| candidates[rng.gen_range(0..candidates.len())])
+            .collect()
+    }
+
+    fn aggregate(&self, scores: Vec<Vec<T>>) -> Vec<T> {
+        scores
+            .into_iter()
+            .map(|scores| {
+                if scores.is_empty() {
+                    T::zero()
+                } else {
+                    self.aggregation.aggregate(scores)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the indices sorted from the best to the worst score so far
+    fn ranking(&self, scores: &[Vec<T>]) -> Vec<usize> {
+        let current = self.aggregate(scores.to_vec());
+        let mut ranking: Vec<usize> = (0..scores.len()).collect();
+        ranking.shuffle(&mut rand::thread_rng());
+        ranking.sort_by(|&a, &b| {
+            current[b]
+                .partial_cmp(&current[a])
+                .unwrap_or(Ordering::Equal)
+        });
+        ranking
+    }
+
+    /// Scores a population, and the opposing population when co-evolving
+    ///
+    /// Matches against the hall of fame only score the network of the population
+    fn evaluate<G: CompetitiveGame<T>>(
+        &self,
+        game: &G,
+        population: &[NeuralNetwork<T>],
+        opponents: Opponents<T>,
+        hall_of_fame: &[NeuralNetwork<T>],
+    ) -> (Vec<T>, Vec<T>) {
+        let opponent_population = match opponents {
+            Opponents::Same => population,
+            Opponents::Other(opponents) => opponents,
+        };
+        let same = matches!(opponents, Opponents::Same);
+        let mut scores = vec![Vec::new(); population.len()];
+        let mut opponent_scores = vec![Vec::new(); opponent_population.len()];
+        let record = |pairs: &[(usize, usize)],
+                      results: Vec<(T, T)>,
+                      scores: &mut Vec<Vec<T>>,
+                      opponent_scores: &mut Vec<Vec<T>>| {
+            for (&(a, b), (score_a, score_b)) in pairs.iter().zip(results) {
+                scores[a].push(score_a);
+                if same {
+                    scores[b].push(score_b);
+                } else {
+                    opponent_scores[b].push(score_b);
+                }
+            }
+        };
+        let schedule = match self.schedule {
+            PairingSchedule::HallOfFame(k) if hall_of_fame.is_empty() => {
+                PairingSchedule::RandomOpponents(k)
+            }
+            schedule => schedule,
+        };
+        match schedule {
+            PairingSchedule::RoundRobin => {
+                let pairs: Vec<(usize, usize)> = (0..population.len())
+                    .flat_map(|a| {
+                        let first_opponent = if same { a + 1 } else { 0 };
+                        (first_opponent..opponent_population.len()).map(move |b| (a, b))
+                    })
+                    .collect();
+                let results = Self::play_matches(game, population, opponent_population, &pairs);
+                record(&pairs, results, &mut scores, &mut opponent_scores);
+            }
+            PairingSchedule::RandomOpponents(k) => {
+                let mut pairs: Vec<(usize, usize)> = (0..population.len())
+                    .flat_map(|a| {
+                        let excluded = if same { Some(a) } else { None };
+                        Self::random_opponents(opponent_population.len(), k, excluded)
+                            .into_iter()
+                            .map(move |b| (a, b))
+                    })
+                    .collect();
+                if !same {
+                    // The opposing population chooses its opponents too
+                    pairs.extend((0..opponent_population.len()).flat_map(|b| {
+                        Self::random_opponents(population.len(), k, None)
+                            .into_iter()
+                            .map(move |a| (a, b))
+                    }));
+                }
+                let results = Self::play_matches(game, population, opponent_population, &pairs);
+                record(&pairs, results, &mut scores, &mut opponent_scores);
+            }
+            PairingSchedule::Swiss { rounds } => {
+                for _ in 0..rounds.max(1) {
+                    let ranking = self.ranking(&scores);
+                    let pairs: Vec<(usize, usize)> = if same {
+                        ranking
+                            .chunks_exact(2)
+                            .map(|pair| (pair[0], pair[1]))
+                            .collect()
+                    } else {
+                        let opponent_ranking = self.ranking(&opponent_scores);
+                        ranking
+                            .iter()
+                            .zip(opponent_ranking.iter().cycle())
+                            .map(|(&a, &b)| (a, b))
+                            .collect()
+                    };
+                    let results = Self::play_matches(game, population, opponent_population, &pairs);
+                    let round: Vec<T> = results.iter().flat_map(|&(a, b)| [a, b]).collect();
+                    record(&pairs, results, &mut scores, &mut opponent_scores);
+                    // With an odd population the last network has a bye, scored at the median
+                    // of the round
+                    if same && ranking.len() % 2 == 1 && !round.is_empty() {
+                        scores[ranking[ranking.len() - 1]]
+                            .push(Aggregation::Median.aggregate(round));
+                    }
+                }
+            }
+            PairingSchedule::HallOfFame(k) => {
+                let pairs: Vec<(usize, usize)> = (0..population.len())
+                    .flat_map(|a| {
+                        Self::random_opponents(hall_of_fame.len(), k, None)
+                            .into_iter()
+                            .map(move |b| (a, b))
+                    })
+                    .collect();
+                let results = Self::play_matches(game, population, hall_of_fame, &pairs);
+                for (&(a, _), (score, _)) in pairs.iter().zip(results) {
+                    scores[a].push(score);
+                }
+            }
+        }
+        (self.aggregate(scores), self.aggregate(opponent_scores))
+    }
+}
+
+macro_rules! tournament_setters {
+    () => {
+        /// Sets how the opponents of every network are chosen
+        ///
+        /// # Arguments
+        ///
+        /// `schedule` - The pairing schedule, defaults to 5 random opponents
+        pub fn schedule(mut self, schedule: PairingSchedule) -> Self {
+            self.tournament.schedule = schedule;
+            self
+        }
+
+        /// Sets how the scores of the matches of a network are combined into its fitness
+        ///
+        /// # Arguments
+        ///
+        /// `aggregation` - Mean, Min or Median, defaults to Mean
+        pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+            self.tournament.aggregation = aggregation;
+            self
+        }
+    };
+}
+
+/// A `Game` scoring every network by matches against the other networks of its population
+///
+/// The `HallOfFame` schedule plays against the hall of fame of the `Train`, which must be enabled
+/// with `Train::hall_of_fame`
+///
+/// ```
+/// use neat_gru::evaluation::competitive::{CompetitiveEvaluation, PairingSchedule};
+/// use neat_gru::neural_network::NeuralNetwork;
+/// use neat_gru::train::hall_of_fame::HallOfFame;
+/// use neat_gru::train::Train;
+///
+/// // The highest output wins
+/// let game = |a: &mut NeuralNetwork<f64>, b: &mut NeuralNetwork<f64>| {
+///     let (a, b) = (a.compute(&[1.0])[0], b.compute(&[1.0])[0]);
+///     if a > b { (1.0, 0.0) } else { (0.0, 1.0) }
+/// };
+/// let mut evaluation = CompetitiveEvaluation::new(game).schedule(PairingSchedule::HallOfFame(5));
+/// let mut runner = Train::new(&mut evaluation);
+/// runner
+///     .inputs(1)
+///     .outputs(1)
+///     .iterations(2)
+///     .max_individuals(20)
+///     .hall_of_fame(HallOfFame::new(10));
+/// runner.start().unwrap();
+/// ```
+pub struct CompetitiveEvaluation<T, G>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    G: CompetitiveGame<T>,
+{
+    pub game: G,
+    tournament: Tournament<T>,
+    nets: Vec<NeuralNetwork<T>>,
+    hall_of_fame: Vec<NeuralNetwork<T>>,
+}
+
+impl<T, G> CompetitiveEvaluation<T, G>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+    G: CompetitiveGame<T>,
+{
+    /// Default values are:
+    /// - schedule -> 5 random opponents
+    /// - aggregation -> Mean
+    ///
+    /// # Arguments
+    ///
+    /// `game` - Plays a match between two networks
+    pub fn new(game: G) -> CompetitiveEvaluation<T, G> {
+        CompetitiveEvaluation {
+            game,
+            tournament: Tournament::new(),
+            nets: Vec::new(),
+            hall_of_fame: Vec::new(),
+        }
+    }
+
+    tournament_setters!();
+
+    /// Returns the networks of the hall of fame given by the training, the best first
+    pub fn hall_of_fame(&self) -> &[NeuralNetwork<T>] {
+        &self.hall_of_fame
+    }
+}
+
+impl<T, G> Game<T> for CompetitiveEvaluation<T, G>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+    G: CompetitiveGame<T>,
+{
+    fn run_generation(&mut self) -> Vec<T> {
+        let (fitness, _) =
+            self.tournament
+                .evaluate(&self.game, &self.nets, Opponents::Same, &self.hall_of_fame);
+        fitness
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) {
+        self.nets = nets;
+    }
+
+    fn reset_hall_of_fame(&mut self, hall_of_fame: &[HallOfFameMember<T>]) {
+        self.hall_of_fame = hall_of_fame.iter().map(HallOfFameMember::network).collect();
+    }
+
+    fn post_training(&mut self, history: Vec<HistoricTopologyLazy<T>>) {
+        self.game.post_training(history);
+    }
+}
+
+impl<T, G> CompetitiveGame<T> for G
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    G: Fn(&mut NeuralNetwork<T>, &mut NeuralNetwork<T>) -> (T, T) + Sync,
+{
+    fn play(&self, a: &mut NeuralNetwork<T>, b: &mut NeuralNetwork<T>) -> (T, T) {
+        self(a, b)
+    }
+}
+
+/// The `Game` of a population trained by `Coevolution`, it only holds the networks
+pub struct Population<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    nets: Vec<NeuralNetwork<T>>,
+    history: Vec<HistoricTopologyLazy<T>>,
+    hall_of_fame: Vec<HallOfFameMember<T>>,
+}
+
+impl<T> Population<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    pub fn new() -> Population<T> {
+        Population {
+            nets: Vec::new(),
+            history: Vec::new(),
+            hall_of_fame: Vec::new(),
+        }
+    }
+
+    /// Returns the history given to `post_training` at the end of the training
+    pub fn history(&self) -> &[HistoricTopologyLazy<T>] {
+        &self.history
+    }
+
+    /// Returns the hall of fame given to `post_training_hall_of_fame` at the end of the training
+    pub fn hall_of_fame(&self) -> &[HallOfFameMember<T>] {
+        &self.hall_of_fame
+    }
+}
+
+impl<T> Default for Population<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Game<T> for Population<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// The networks of a co-evolving population are evaluated by `Coevolution::run`, a
+    /// `Population` trained on its own scores every network 0
+    fn run_generation(&mut self) -> Vec<T> {
+        vec![T::zero(); self.nets.len()]
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) {
+        self.nets = nets;
+    }
+
+    fn post_training(&mut self, history: Vec<HistoricTopologyLazy<T>>) {
+        self.history = history;
+    }
+
+    fn post_training_hall_of_fame(&mut self, hall_of_fame: Vec<HallOfFameMember<T>>) {
+        self.hall_of_fame = hall_of_fame;
+    }
+}
+
+/// Trains two populations against each other, e.g. predators and preys
+///
+/// The networks of the first population are always the first player of `play`
+pub struct Coevolution<T, G>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    G: CompetitiveGame<T>,
+{
+    pub game: G,
+    tournament: Tournament<T>,
+}
+
+impl<T, G> Coevolution<T, G>
+where
+    T: Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    G: CompetitiveGame<T>,
+{
+    /// Default values are:
+    /// - schedule -> 5 random opponents
+    /// - aggregation -> Mean
+    ///
+    /// # Arguments
+    ///
+    /// `game` - Plays a match between a network of each population
+    pub fn new(game: G) -> Coevolution<T, G> {
+        Coevolution {
+            game,
+            tournament: Tournament::new(),
+        }
+    }
+
+    tournament_setters!();
+
+    /// Trains both populations until one of them runs all its iterations, is stopped or dies
+    ///
+    /// With the `HallOfFame` schedule, each population plays against the hall of fame of the
+    /// training of the other one, which must be enabled with `Train::hall_of_fame`
+    ///
+    /// The fitness is given to `Train::step_with_results`, the networks can't be evaluated again:
+    /// returns an InvalidConfiguration Error if noise handling or local search is enabled on
+    /// either training, or if the hall of fame of a training is missing
+    ///
+    /// # Arguments
+    ///
+    /// `first` - The training of the first population
+    ///
+    /// `second` - The training of the second population
+    pub fn run<'a, 'b, S1, S2>(
+        &mut self,
+        first: &mut Train<'a, Population<T>, T, S1>,
+        second: &mut Train<'b, Population<T>, T, S2>,
+    ) -> Result<(TrainingOutcome, TrainingOutcome), TrainingError>
+    where
+        S1: BorrowMut<Population<T>>,
+        S2: BorrowMut<Population<T>>,
+    {
+        let mut errors = Vec::new();
+        let needs_hall_of_fame = matches!(self.tournament.schedule, PairingSchedule::HallOfFame(_));
+        check_config(first.config(), "first", needs_hall_of_fame, &mut errors);
+        check_config(second.config(), "second", needs_hall_of_fame, &mut errors);
+        if !errors.is_empty() {
+            return Err(TrainingError::InvalidConfiguration(errors));
+        }
+        first.init()?;
+        second.init()?;
+        while first.should_continue() && second.should_continue() {
+            let (first_fitness, second_fitness) = {
+                let first_nets = &first.simulation.borrow().nets;
+                let second_nets = &second.simulation.borrow().nets;
+                match self.tournament.schedule {
+                    PairingSchedule::HallOfFame(_) => {
+                        let networks = |members: &[HallOfFameMember<T>]| -> Vec<NeuralNetwork<T>> {
+                            members.iter().map(HallOfFameMember::network).collect()
+                        };
+                        let first_hall = networks(first.hall_of_fame_members());
+                        let second_hall = networks(second.hall_of_fame_members());
+                        let (first_fitness, _) = self.tournament.evaluate(
+                            &self.game,
+                            first_nets,
+                            Opponents::Other(second_nets),
+                            &second_hall,
+                        );
+                        let second_fitness =
+                            self.evaluate_against_first(first_nets, second_nets, &first_hall);
+                        (first_fitness, second_fitness)
+                    }
+                    _ => self.tournament.evaluate(
+                        &self.game,
+                        first_nets,
+                        Opponents::Other(second_nets),
+                        &[],
+                    ),
+                }
+            };
+            let first_report = first.step_with_results(first_fitness)?;
+            let second_report = second.step_with_results(second_fitness)?;
+            if first_report.extinct || second_report.extinct {
+                break;
+            }
+        }
+        Ok((first.finish_training()?, second.finish_training()?))
+    }
+
+    /// Scores the second population against the hall of fame of the first one, keeping the
+    /// networks of the first population as first players
+    fn evaluate_against_first(
+        &self,
+        first_nets: &[NeuralNetwork<T>],
+        second_nets: &[NeuralNetwork<T>],
+        first_hall: &[NeuralNetwork<T>],
+    ) -> Vec<T> {
+        let k = match self.tournament.schedule {
+            PairingSchedule::HallOfFame(k) => k,
+            _ => 0,
+        };
+        let opponents = if first_hall.is_empty() {
+            first_nets
+        } else {
+            first_hall
+        };
+        let pairs: Vec<(usize, usize)> = (0..second_nets.len())
+            .flat_map(|b| {
+                Tournament::<T>::random_opponents(opponents.len(), k, None)
+                    .into_iter()
+                    .map(move |a| (a, b))
+            })
+            .collect();
+        let results = Tournament::play_matches(&self.game, opponents, second_nets, &pairs);
+        let mut scores = vec![Vec::new(); second_nets.len()];
+        for (&(_, b), (_, score)) in pairs.iter().zip(results) {
+            scores[b].push(score);
+        }
+        self.tournament.aggregate(scores)
+    }
+}
+
+/// Pushes an error for each option of `config` needing to evaluate the networks again, and for
+/// a missing hall of fame when the schedule plays against it
+fn check_config<T>(
+    config: &TrainConfig<T>,
+    population: &str,
+    needs_hall_of_fame: bool,
+    errors: &mut Vec<ConfigurationError>,
+) where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    if needs_hall_of_fame && config.hall_of_fame.is_none() {
+        errors.push(ConfigurationError {
+            parameter: "hall_of_fame",
+            reason: format!(
+                "required by the HallOfFame schedule, missing on the {} population",
+                population
+            ),
+        });
+    }
+    if config.noise_handling.is_some() {
+        errors.push(ConfigurationError {
+            parameter: "noise_handling",
+            reason: format!(
+                "not supported by Coevolution, set on the {} population",
+                population
+            ),
+        });
+    }
+    if config.local_search.is_some() {
+        errors.push(ConfigurationError {
+            parameter: "local_search",
+            reason: format!(
+                "not supported by Coevolution, set on the {} population",
+                population
+            ),
+        });
+    }
+}
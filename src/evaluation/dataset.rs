@@ -0,0 +1,420 @@
+//! Evaluation of the networks on a supervised dataset of input and target sequences
+use crate::game::{Game, NetworkInfo};
+use crate::neural_network::fine_tuning::Sequence;
+use crate::neural_network::NeuralNetwork;
+use crate::train::error::TrainingError;
+use crate::train::HistoricTopologyLazy;
+use num::Float;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+/// How the outputs of a network are compared to the targets, the fitness of a network is the
+/// opposite of its mean loss, or its accuracy
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Loss {
+    #[default]
+    MeanSquaredError,
+    MeanAbsoluteError,
+    /// Binary cross-entropy of `(output + 1) / 2` with one output, categorical cross-entropy
+    /// of the softmax of the outputs otherwise. The targets are 0 or 1
+    CrossEntropy,
+    /// Fraction of the steps where the output is above 0 when the target is above 0.5 with one
+    /// output, or where the largest output is the one of the largest target otherwise
+    Accuracy,
+}
+
+impl Loss {
+    /// Score of one step, the higher the better
+    pub(crate) fn score<T: Float>(&self, outputs: &[T], targets: &[T]) -> T {
+        let count = T::from(outputs.len().max(1)).unwrap();
+        let pairs = outputs.iter().zip(targets).map(|(&o, &t)| (o, t));
+        match self {
+            Loss::MeanSquaredError => {
+                -pairs.fold(T::zero(), |acc, (o, t)| acc + (o - t) * (o - t)) / count
+            }
+            Loss::MeanAbsoluteError => {
+                -pairs.fold(T::zero(), |acc, (o, t)| acc + (o - t).abs()) / count
+            }
+            Loss::CrossEntropy => {
+                let epsilon = T::from(1e-7).unwrap();
+                let clamp = |p: T| p.max(epsilon).min(T::one() - epsilon);
+                if outputs.len() == 1 {
+                    let p = clamp((outputs[0] + T::one()) / T::from(2).unwrap());
+                    let t = targets[0];
+                    t * p.ln() + (T::one() - t) * (T::one() - p).ln()
+                } else {
+                    let max = outputs.iter().fold(T::neg_infinity(), |acc, &o| acc.max(o));
+                    let sum = outputs
+                        .iter()
+                        .fold(T::zero(), |acc, &o| acc + (o - max).exp());
+                    pairs.fold(T::zero(), |acc, (o, t)| {
+                        acc + t * clamp((o - max).exp() / sum).ln()
+                    })
+                }
+            }
+            Loss::Accuracy => {
+                let correct = if outputs.len() == 1 {
+                    (outputs[0] > T::zero()) == (targets[0] > T::from(0.5).unwrap())
+                } else {
+                    argmax(outputs) == argmax(targets)
+                };
+                if correct {
+                    T::one()
+                } else {
+                    T::zero()
+                }
+            }
+        }
+    }
+}
+
+fn argmax<T: Float>(values: &[T]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, T::neg_infinity()), |(best, max), (index, &value)| {
+            if value > max {
+                (index, value)
+            } else {
+                (best, max)
+            }
+        })
+        .0
+}
+
+fn invalid_dataset(reason: String) -> TrainingError {
+    TrainingError::InvalidDataset(reason)
+}
+
+fn parse_error(
+    line: usize,
+    path: &Path,
+    err: impl std::error::Error + Send + Sync + 'static,
+) -> TrainingError {
+    TrainingError::Serialization {
+        context: format!("line {} of {}", line, path.display()),
+        source: Box::new(err),
+    }
+}
+
+/// Reads a CSV file, the first line is a header. Every other line is one step of a sequence:
+/// the id of the sequence, the inputs and the targets. The consecutive lines with the same id
+/// form a sequence
+///
+/// # Arguments
+///
+/// `path` - The CSV file
+///
+/// `inputs` - The number of inputs of every step, the other columns are the targets
+pub fn read_csv<T, P>(path: P, inputs: usize) -> Result<Vec<Sequence<T>>, TrainingError>
+where
+    T: Float,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(TrainingError::checkpoint(path))?;
+    let mut sequences: Vec<Sequence<T>> = Vec::new();
+    let mut last_id = None;
+    for (index, line) in content.lines().enumerate().skip(1) {
+        let line_number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut fields = line.split(',').map(str::trim);
+        let id = fields.next().unwrap_or_default();
+        let values = fields
+            .map(|field| {
+                field
+                    .parse::<f64>()
+                    .map(|value| T::from(value).unwrap())
+                    .map_err(|err| parse_error(line_number, path, err))
+            })
+            .collect::<Result<Vec<T>, TrainingError>>()?;
+        if values.len() <= inputs {
+            let reason = format!("expected {} inputs and some targets", inputs);
+            let err = io::Error::new(io::ErrorKind::InvalidData, reason);
+            return Err(parse_error(line_number, path, err));
+        }
+        if last_id != Some(id) {
+            sequences.push(Sequence::new(Vec::new(), Vec::new()));
+            last_id = Some(id);
+        }
+        if let Some(sequence) = sequences.last_mut() {
+            sequence.inputs.push(values[..inputs].to_vec());
+            sequence.targets.push(values[inputs..].to_vec());
+        }
+    }
+    Ok(sequences)
+}
+
+/// Reads a JSON lines file, every line is a sequence: `{"inputs": [[...], ...], "targets":
+/// [[...], ...]}`
+///
+/// # Arguments
+///
+/// `path` - The JSON lines file
+pub fn read_json_lines<T, P>(path: P) -> Result<Vec<Sequence<T>>, TrainingError>
+where
+    T: Float + DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path).map_err(TrainingError::checkpoint(path))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|err| parse_error(index + 1, path, err))
+        })
+        .collect()
+}
+
+/// Shuffles the sequences and splits them into a training and a validation set
+///
+/// # Arguments
+///
+/// `sequences` - The whole dataset
+///
+/// `validation_fraction` - The fraction of the sequences in the validation set, between 0 and 1
+pub fn split<T>(
+    mut sequences: Vec<Sequence<T>>,
+    validation_fraction: f64,
+) -> (Vec<Sequence<T>>, Vec<Sequence<T>>) {
+    sequences.shuffle(&mut thread_rng());
+    let validation =
+        (sequences.len() as f64 * validation_fraction.clamp(0.0, 1.0)).round() as usize;
+    let training = sequences.split_off(validation);
+    (training, sequences)
+}
+
+/// A `Game` scoring every network on a dataset of sequences, the state of the networks is reset
+/// before every sequence
+///
+/// The fitness is the mean score of the sequences, where the score of a sequence is the mean
+/// score of its steps. After the evaluation of every generation, the best network is also scored
+/// on the validation set, if any
+///
+/// ```
+/// use neat_gru::evaluation::dataset::{DatasetGame, Loss};
+/// use neat_gru::neural_network::fine_tuning::Sequence;
+/// use neat_gru::train::Train;
+///
+/// let xor = vec![
+///     Sequence::new(vec![vec![0.0, 0.0]], vec![vec![0.0]]),
+///     Sequence::new(vec![vec![0.0, 1.0]], vec![vec![1.0]]),
+///     Sequence::new(vec![vec![1.0, 0.0]], vec![vec![1.0]]),
+///     Sequence::new(vec![vec![1.0, 1.0]], vec![vec![0.0]]),
+/// ];
+/// let mut game = DatasetGame::new(xor.clone(), Loss::CrossEntropy)
+///     .unwrap()
+///     .validation(xor)
+///     .unwrap();
+/// let (inputs, outputs) = (game.inputs(), game.outputs());
+/// {
+///     let mut runner = Train::new(&mut game);
+///     runner.inputs(inputs).outputs(outputs).iterations(2).max_individuals(20);
+///     runner.start().unwrap();
+/// }
+/// assert_eq!(game.validation_scores().len(), 2);
+/// ```
+pub struct DatasetGame<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    training: Vec<Sequence<T>>,
+    validation: Vec<Sequence<T>>,
+    loss: Loss,
+    inputs: usize,
+    outputs: usize,
+    validation_scores: Vec<T>,
+    nets: Vec<NeuralNetwork<T>>,
+    /// Generation and evaluation of the networks, see `NetworkInfo`, `None` when they were given
+    /// by `reset_players`
+    evaluation: Option<(usize, usize)>,
+    /// Generation of the last validation score
+    validated_generation: Option<usize>,
+}
+
+impl<T> DatasetGame<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+{
+    /// Fails if the training set is empty, or if the sequences don't all have the same number of
+    /// inputs and targets, with one target per input
+    ///
+    /// # Arguments
+    ///
+    /// `training` - The sequences the fitness is computed on
+    ///
+    /// `loss` - Compares the outputs of the networks to the targets
+    pub fn new(training: Vec<Sequence<T>>, loss: Loss) -> Result<DatasetGame<T>, TrainingError> {
+        let first = training
+            .iter()
+            .flat_map(|sequence| sequence.inputs.iter().zip(&sequence.targets))
+            .next()
+            .ok_or_else(|| invalid_dataset("the training set has no steps".to_string()))?;
+        let (inputs, outputs) = (first.0.len(), first.1.len());
+        let game = DatasetGame {
+            training,
+            validation: Vec::new(),
+            loss,
+            inputs,
+            outputs,
+            validation_scores: Vec::new(),
+            nets: Vec::new(),
+            evaluation: None,
+            validated_generation: None,
+        };
+        game.check(&game.training)?;
+        Ok(game)
+    }
+
+    /// Sets the validation set, the best network of every generation is scored on it
+    ///
+    /// # Arguments
+    ///
+    /// `validation` - Sequences with the same number of inputs and targets as the training set
+    pub fn validation(mut self, validation: Vec<Sequence<T>>) -> Result<Self, TrainingError> {
+        self.check(&validation)?;
+        self.validation = validation;
+        Ok(self)
+    }
+
+    /// Number of inputs of every step, the number of inputs of the training
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// Number of targets of every step, the number of outputs of the training
+    pub fn outputs(&self) -> usize {
+        self.outputs
+    }
+
+    /// The validation score of the best network of every generation, empty without validation
+    /// set
+    ///
+    /// The networks evaluated again by noise handling or local search aren't scored, a generation
+    /// evaluated again by `InvalidFitnessPolicy::Retry` replaces its score
+    pub fn validation_scores(&self) -> &[T] {
+        &self.validation_scores
+    }
+
+    /// Mean score of the network on the sequences
+    ///
+    /// # Arguments
+    ///
+    /// `net` - The network to score, its state is reset before every sequence
+    ///
+    /// `sequences` - Sequences with the same number of inputs and targets as the training set
+    pub fn score(&self, net: &mut NeuralNetwork<T>, sequences: &[Sequence<T>]) -> T {
+        score(self.loss, net, sequences)
+    }
+
+    fn check(&self, sequences: &[Sequence<T>]) -> Result<(), TrainingError> {
+        for (index, sequence) in sequences.iter().enumerate() {
+            if sequence.inputs.len() != sequence.targets.len() {
+                return Err(invalid_dataset(format!(
+                    "sequence {} has {} inputs but {} targets",
+                    index,
+                    sequence.inputs.len(),
+                    sequence.targets.len()
+                )));
+            }
+            if sequence
+                .inputs
+                .iter()
+                .zip(&sequence.targets)
+                .any(|(inputs, targets)| {
+                    inputs.len() != self.inputs || targets.len() != self.outputs
+                })
+            {
+                return Err(invalid_dataset(format!(
+                    "every step of sequence {} must have {} inputs and {} targets",
+                    index, self.inputs, self.outputs
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn score<T>(loss: Loss, net: &mut NeuralNetwork<T>, sequences: &[Sequence<T>]) -> T
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    let mut total = T::zero();
+    let mut count = 0;
+    for sequence in sequences
+        .iter()
+        .filter(|sequence| !sequence.inputs.is_empty())
+    {
+        net.reset_state();
+        let mut sequence_total = T::zero();
+        for (inputs, targets) in sequence.inputs.iter().zip(&sequence.targets) {
+            let outputs = net.compute(inputs);
+            sequence_total += loss.score(&outputs, targets);
+        }
+        total += sequence_total / T::from(sequence.inputs.len()).unwrap();
+        count += 1;
+    }
+    if count == 0 {
+        return T::zero();
+    }
+    total / T::from(count).unwrap()
+}
+
+impl<T> Game<T> for DatasetGame<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+{
+    fn run_generation(&mut self) -> Vec<T> {
+        let (training, loss) = (&self.training, self.loss);
+        let fitness: Vec<T> = cond_iter_mut!(self.nets)
+            .map(|net| score(loss, net, training))
+            .collect();
+        let best = fitness
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| !value.is_nan())
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(index, _)| index);
+        // Only the evaluation of the whole generation is validated
+        let (generation, evaluation) = match self.evaluation {
+            Some((generation, evaluation)) => (Some(generation), evaluation),
+            None => (None, 0),
+        };
+        if let (Some(best), false, 0) = (best, self.validation.is_empty(), evaluation) {
+            let validation_score = score(loss, &mut self.nets[best], &self.validation);
+            log::info!("VALIDATION SCORE: {}", validation_score);
+            match self.validation_scores.last_mut() {
+                Some(last) if generation.is_some() && generation == self.validated_generation => {
+                    *last = validation_score
+                }
+                _ => self.validation_scores.push(validation_score),
+            }
+            self.validated_generation = generation;
+        }
+        fitness
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) {
+        self.nets = nets;
+        self.evaluation = None;
+    }
+
+    fn reset_players_with_info(&mut self, players: Vec<(NetworkInfo, NeuralNetwork<T>)>) {
+        self.evaluation = players
+            .first()
+            .map(|(info, _)| (info.generation, info.evaluation));
+        self.nets = players.into_iter().map(|(_, net)| net).collect();
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<T>>) {}
+}
@@ -0,0 +1,177 @@
+//! Evaluation of each network on its own, without implementing `Game`
+use crate::game::Game;
+use crate::neural_network::NeuralNetwork;
+use crate::train::HistoricTopologyLazy;
+use num::Float;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+/// Trait to implement when each network is evaluated independently of the others
+///
+/// Implemented for every `Fn(&mut NeuralNetwork<T>) -> T + Sync` closure
+pub trait Evaluator<T>: Sync
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Runs one episode, returns the fitness of the network
+    ///
+    /// # Arguments
+    ///
+    /// `net` - The network to evaluate, its state is reset before each episode
+    fn evaluate(&self, net: &mut NeuralNetwork<T>) -> T;
+
+    /// Function to be run at the end of the training
+    ///
+    /// # Arguments
+    ///
+    /// `history` - The best topologies of every generation
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<T>>) {}
+}
+
+impl<T, E> Evaluator<T> for E
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    E: Fn(&mut NeuralNetwork<T>) -> T + Sync,
+{
+    fn evaluate(&self, net: &mut NeuralNetwork<T>) -> T {
+        self(net)
+    }
+}
+
+/// How the fitness of the episodes of a network are combined
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregation {
+    #[default]
+    Mean,
+    /// Worst episode, rewards networks that are good in every episode
+    Min,
+    /// Robust to a few outlier episodes
+    Median,
+}
+
+impl Aggregation {
+    pub(crate) fn aggregate<T: Float>(&self, mut fitness: Vec<T>) -> T {
+        if fitness.is_empty() {
+            return T::nan();
+        }
+        match self {
+            Aggregation::Mean => {
+                fitness.iter().fold(T::zero(), |acc, &value| acc + value)
+                    / T::from(fitness.len()).unwrap()
+            }
+            Aggregation::Min => fitness.into_iter().fold(T::infinity(), T::min),
+            Aggregation::Median => {
+                fitness.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                let middle = fitness.len() / 2;
+                if fitness.len().is_multiple_of(2) {
+                    (fitness[middle - 1] + fitness[middle]) / T::from(2).unwrap()
+                } else {
+                    fitness[middle]
+                }
+            }
+        }
+    }
+}
+
+/// A `Game` running an `Evaluator` on every network in parallel
+///
+/// ```
+/// use neat_gru::evaluation::evaluator::{Aggregation, EvaluatorGame};
+/// use neat_gru::neural_network::NeuralNetwork;
+/// use neat_gru::train::Train;
+///
+/// let evaluator = |net: &mut NeuralNetwork<f64>| {
+///     let output = net.compute(&[0.5, 1.0]);
+///     -(output[0] - 1.0).abs()
+/// };
+/// let mut game = EvaluatorGame::new(evaluator)
+///     .episodes(3)
+///     .aggregation(Aggregation::Median);
+/// let mut runner = Train::new(&mut game);
+/// runner.inputs(2).outputs(1).iterations(2).max_individuals(20);
+/// runner.start().unwrap();
+/// ```
+pub struct EvaluatorGame<T, E>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    E: Evaluator<T>,
+{
+    pub evaluator: E,
+    episodes: usize,
+    aggregation: Aggregation,
+    nets: Vec<NeuralNetwork<T>>,
+}
+
+impl<T, E> EvaluatorGame<T, E>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+    E: Evaluator<T>,
+{
+    /// Default values are:
+    /// - episodes -> 1
+    /// - aggregation -> Mean
+    ///
+    /// # Arguments
+    ///
+    /// `evaluator` - Evaluates one network
+    pub fn new(evaluator: E) -> EvaluatorGame<T, E> {
+        EvaluatorGame {
+            evaluator,
+            episodes: 1,
+            aggregation: Aggregation::Mean,
+            nets: Vec::new(),
+        }
+    }
+
+    /// Sets the number of episodes run by each network every generation
+    ///
+    /// # Arguments
+    ///
+    /// `episodes` - Number of episodes, at least 1
+    pub fn episodes(mut self, episodes: usize) -> Self {
+        self.episodes = episodes.max(1);
+        self
+    }
+
+    /// Sets how the fitness of the episodes are combined
+    ///
+    /// # Arguments
+    ///
+    /// `aggregation` - Mean, Min or Median
+    pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+}
+
+impl<T, E> Game<T> for EvaluatorGame<T, E>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+    E: Evaluator<T>,
+{
+    fn run_generation(&mut self) -> Vec<T> {
+        let evaluator = &self.evaluator;
+        let (episodes, aggregation) = (self.episodes, self.aggregation);
+        cond_iter_mut!(self.nets)
+            .map(|net| {
+                let fitness = (0..episodes)
+                    .map(|_| {
+                        net.reset_state();
+                        evaluator.evaluate(net)
+                    })
+                    .collect();
+                aggregation.aggregate(fitness)
+            })
+            .collect()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) {
+        self.nets = nets;
+    }
+
+    fn post_training(&mut self, history: Vec<HistoricTopologyLazy<T>>) {
+        self.evaluator.post_training(history);
+    }
+}
@@ -0,0 +1,345 @@
+//! Evaluation of the networks by an external process
+//!
+//! The trainer and the evaluator exchange one JSON object per line, the evaluator reads
+//! on its stdin and writes on its stdout.
+//!
+//! At every generation, the trainer sends the topologies of the networks, serialized the same way
+//! as `Topology::to_string`:
+//!
+//! ```json
+//! {"type":"generation","generation":0,"networks":[{"biases":[...],"genes":[...]}, ...]}
+//! ```
+//!
+//! The evaluator answers with one fitness per network, in the same order:
+//!
+//! ```json
+//! {"fitness":[1.5, 0.2, ...]}
+//! ```
+//!
+//! Or with an error, which stops the training:
+//!
+//! ```json
+//! {"error":"simulator crashed"}
+//! ```
+//!
+//! At the end of the training the trainer sends the best topology, the evaluator should then exit:
+//!
+//! ```json
+//! {"type":"end","best":{"biases":[...],"genes":[...]}}
+//! ```
+use crate::game::Game;
+use crate::neural_network::NeuralNetwork;
+use crate::topology::Topology;
+use crate::topology::TopologySmrtPtr;
+use crate::train::HistoricTopologyLazy;
+use num::Float;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+const BEST_SO_FAR_FILE: &str = "best-so-far.json";
+const HISTORY_FILE: &str = "history.jsonl";
+const BEST_FILE: &str = "best.json";
+
+/// Message sent to the evaluator
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EvaluatorRequest<'a> {
+    Generation {
+        generation: usize,
+        networks: Cow<'a, [Value]>,
+    },
+    End {
+        best: Option<Cow<'a, Value>>,
+    },
+}
+
+/// Message received from the evaluator
+#[derive(Serialize, Deserialize)]
+#[serde(bound(deserialize = "F: Deserialize<'de>"))]
+pub struct EvaluatorResponse<F> {
+    #[serde(default)]
+    pub fitness: Vec<F>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// The best topology after a generation, it can't be used to resume the training
+#[derive(Serialize)]
+struct BestSoFar<'a, F> {
+    generation: usize,
+    best_fitness: F,
+    best: &'a Value,
+}
+
+#[derive(Debug)]
+pub enum ExternalEvaluatorError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The evaluator answered with an error
+    Evaluator(String),
+    /// The evaluator closed its stdout
+    Closed,
+}
+
+impl std::error::Error for ExternalEvaluatorError {}
+
+impl fmt::Display for ExternalEvaluatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalEvaluatorError::Io(err) => write!(f, "Evaluator I/O error: {}", err),
+            ExternalEvaluatorError::Json(err) => write!(f, "Invalid evaluator message: {}", err),
+            ExternalEvaluatorError::Evaluator(err) => write!(f, "Evaluator error: {}", err),
+            ExternalEvaluatorError::Closed => write!(f, "The evaluator exited"),
+        }
+    }
+}
+
+impl From<io::Error> for ExternalEvaluatorError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ExternalEvaluatorError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+/// A game whose networks are evaluated by another executable, see the module documentation for
+/// the protocol
+pub struct ExternalEvaluator<F> {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    networks: Vec<Value>,
+    generation: usize,
+    best: Option<(F, Value)>,
+    output_dir: Option<PathBuf>,
+}
+
+impl<F> ExternalEvaluator<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send + Serialize + DeserializeOwned,
+{
+    /// Spawns the evaluator
+    ///
+    /// # Arguments
+    ///
+    /// `command` - The evaluator executable and its arguments, stdin and stdout are overridden
+    pub fn spawn(command: &mut Command) -> Result<ExternalEvaluator<F>, ExternalEvaluatorError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(ExternalEvaluatorError::Closed)?;
+        let stdout = child.stdout.take().ok_or(ExternalEvaluatorError::Closed)?;
+        Ok(ExternalEvaluator {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            networks: Vec::new(),
+            generation: 0,
+            best: None,
+            output_dir: None,
+        })
+    }
+
+    /// Sets the directory where the best topology so far, after every generation, the history
+    /// and the best topology are written
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The output directory, created if needed
+    pub fn output_dir<P: Into<PathBuf>>(mut self, path: P) -> Result<Self, ExternalEvaluatorError> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        self.output_dir = Some(path);
+        Ok(self)
+    }
+
+    /// Returns the best fitness seen so far
+    pub fn best_fitness(&self) -> Option<F> {
+        self.best.as_ref().map(|(fitness, _)| *fitness)
+    }
+
+    /// Sends the networks to the evaluator and waits for their fitness
+    fn evaluate(&mut self) -> Result<Vec<F>, ExternalEvaluatorError> {
+        let request = EvaluatorRequest::Generation {
+            generation: self.generation,
+            networks: Cow::Borrowed(&self.networks),
+        };
+        Self::send(&mut self.stdin, &request)?;
+        let response = self.receive()?;
+        if let Some(error) = response.error {
+            return Err(ExternalEvaluatorError::Evaluator(error));
+        }
+        let generation_best = response
+            .fitness
+            .iter()
+            .zip(self.networks.iter())
+            .filter(|(fitness, _)| fitness.is_finite())
+            .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        if let Some((&fitness, network)) = generation_best {
+            if self.best_fitness().is_none_or(|best| fitness > best) {
+                self.best = Some((fitness, network.clone()));
+            }
+        }
+        self.write_best_so_far()?;
+        Ok(response.fitness)
+    }
+
+    fn set_topologies(
+        &mut self,
+        topologies: &[TopologySmrtPtr<F>],
+    ) -> Result<(), ExternalEvaluatorError> {
+        self.networks = topologies
+            .iter()
+            .map(|topology| serde_json::from_str(&topology.lock().unwrap().to_string()))
+            .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+        Ok(())
+    }
+
+    /// Writes the history and tells the evaluator to exit
+    fn end(&mut self, history: Vec<HistoricTopologyLazy<F>>) -> Result<(), ExternalEvaluatorError> {
+        self.write_history(history)?;
+        let best = self.best.as_ref().map(|(_, best)| Cow::Borrowed(best));
+        Self::send(&mut self.stdin, &EvaluatorRequest::End { best })?;
+        self.child.wait()?;
+        Ok(())
+    }
+
+    fn send(
+        stdin: &mut ChildStdin,
+        request: &EvaluatorRequest,
+    ) -> Result<(), ExternalEvaluatorError> {
+        serde_json::to_writer(&mut *stdin, request)?;
+        stdin.write_all(b"\n")?;
+        stdin.flush()?;
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<EvaluatorResponse<F>, ExternalEvaluatorError> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(ExternalEvaluatorError::Closed);
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    fn write_best_so_far(&self) -> Result<(), ExternalEvaluatorError> {
+        if let (Some(dir), Some((fitness, best))) = (&self.output_dir, &self.best) {
+            let best_so_far = BestSoFar {
+                generation: self.generation,
+                best_fitness: *fitness,
+                best,
+            };
+            fs::write(
+                dir.join(BEST_SO_FAR_FILE),
+                serde_json::to_string(&best_so_far)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_history(
+        &self,
+        history: Vec<HistoricTopologyLazy<F>>,
+    ) -> Result<(), ExternalEvaluatorError> {
+        let dir = match &self.output_dir {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        let mut file = File::create(dir.join(HISTORY_FILE))?;
+        for historic in history {
+            let historic = historic.into_historic()?;
+            let topology: Value = serde_json::from_str(&historic.topology.to_string())?;
+            serde_json::to_writer(
+                &mut file,
+                &serde_json::json!({ "generation": historic.generation, "topology": topology }),
+            )?;
+            file.write_all(b"\n")?;
+        }
+        if let Some((_, best)) = &self.best {
+            fs::write(dir.join(BEST_FILE), serde_json::to_string(best)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl_try_game!(ExternalEvaluator, ExternalEvaluatorError);
+
+impl<F> Drop for ExternalEvaluator<F> {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Runs the evaluator side of the protocol with a `Game`, until the trainer sends the end message
+///
+/// Lets an evaluator written in Rust run in another process or on another machine
+///
+/// # Arguments
+///
+/// `game` - The game evaluating the networks
+///
+/// `input` - Where the requests are read, e.g. stdin
+///
+/// `output` - Where the fitness is written, e.g. stdout
+pub fn serve<F, G, R, W>(
+    game: &mut G,
+    input: R,
+    mut output: W,
+) -> Result<(), ExternalEvaluatorError>
+where
+    F: Float + std::ops::AddAssign + Display + Send + Serialize,
+    G: Game<F>,
+    R: BufRead,
+    W: Write,
+{
+    for line in input.lines() {
+        let request: EvaluatorRequest = serde_json::from_str(&line?)?;
+        let networks = match request {
+            EvaluatorRequest::Generation { networks, .. } => networks,
+            EvaluatorRequest::End { .. } => return Ok(()),
+        };
+        let response = match networks
+            .iter()
+            .map(|network| {
+                let serialized = network.to_string();
+                if Topology::<F>::is_valid_topology_json(&serialized) {
+                    Ok(NeuralNetwork::from_string(&serialized))
+                } else {
+                    Err(format!("Invalid topology: {}", serialized))
+                }
+            })
+            .collect::<Result<Vec<NeuralNetwork<F>>, String>>()
+        {
+            Ok(nets) => {
+                game.reset_players(nets);
+                EvaluatorResponse {
+                    fitness: game.run_generation(),
+                    error: None,
+                }
+            }
+            Err(error) => EvaluatorResponse {
+                fitness: Vec::new(),
+                error: Some(error),
+            },
+        };
+        serde_json::to_writer(&mut output, &response)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    Err(ExternalEvaluatorError::Closed)
+}
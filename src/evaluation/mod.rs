@@ -0,0 +1,59 @@
+// Implements `TryGame` for each float type by forwarding to the `evaluate`, `set_topologies` and
+// `end` methods of the evaluator. The topologies are the ones `Train` built the networks from,
+// the `generation` field is taken from the `NetworkInfo` of the networks. A generic
+// implementation would conflict with the `TryGame` implementation of every `Game`
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! impl_try_game {
+    ($evaluator: ident, $error: ty) => {
+        impl_try_game!($evaluator, $error, f32);
+        impl_try_game!($evaluator, $error, f64);
+    };
+    ($evaluator: ident, $error: ty, $float: ty) => {
+        impl crate::game::TryGame<$float> for $evaluator<$float> {
+            type Error = $error;
+
+            fn try_run_generation(&mut self) -> Result<Vec<$float>, $error> {
+                self.evaluate()
+            }
+
+            fn try_reset_players(
+                &mut self,
+                _nets: Vec<crate::neural_network::NeuralNetwork<$float>>,
+            ) -> Result<(), $error> {
+                Ok(())
+            }
+
+            fn try_reset_players_with_info(
+                &mut self,
+                players: Vec<(
+                    crate::game::NetworkInfo,
+                    crate::neural_network::NeuralNetwork<$float>,
+                )>,
+            ) -> Result<(), $error> {
+                if let Some((info, _)) = players.first() {
+                    self.generation = info.generation;
+                }
+                let topologies: Vec<crate::topology::TopologySmrtPtr<$float>> = players
+                    .iter()
+                    .filter_map(|(_, net)| net.topology().cloned())
+                    .collect();
+                self.set_topologies(&topologies)
+            }
+
+            fn try_post_training(
+                &mut self,
+                history: Vec<crate::train::HistoricTopologyLazy<$float>>,
+            ) -> Result<(), $error> {
+                self.end(history)
+            }
+        }
+    };
+}
+
+pub mod competitive;
+pub mod dataset;
+pub mod evaluator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod external;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod workers;
@@ -0,0 +1,508 @@
+//! Evaluation of the networks by a pool of local worker processes
+//!
+//! The trainer listens on a TCP or Unix socket and spawns the worker command, each worker connects
+//! back to the address found in the `NEAT_GRU_WORKER_ADDRESS` environment variable and evaluates
+//! batches of networks with `run_worker`. Workers speak the protocol of
+//! `neat_gru::evaluation::external` over the socket.
+//!
+//! A worker that crashes or doesn't answer before the timeout is killed and restarted, its batch
+//! is given to another worker.
+use crate::evaluation::external::{serve, EvaluatorRequest, EvaluatorResponse};
+use crate::game::Game;
+use crate::topology::TopologySmrtPtr;
+use crate::train::HistoricTopologyLazy;
+use num::Float;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Environment variable giving the address of the trainer to the workers
+pub const WORKER_ADDRESS_VAR: &str = "NEAT_GRU_WORKER_ADDRESS";
+
+/// Socket used between the trainer and its workers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WorkerTransport {
+    /// TCP on localhost, default
+    #[default]
+    Tcp,
+    /// Unix domain socket in a temporary directory
+    #[cfg(unix)]
+    Unix,
+}
+
+#[derive(Debug)]
+pub enum WorkerError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// A worker answered with an error
+    Worker(String),
+    /// The workers crashed or timed out more than `max_restarts` times
+    TooManyRestarts(usize),
+}
+
+impl std::error::Error for WorkerError {}
+
+impl fmt::Display for WorkerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerError::Io(err) => write!(f, "Worker I/O error: {}", err),
+            WorkerError::Json(err) => write!(f, "Invalid worker message: {}", err),
+            WorkerError::Worker(err) => write!(f, "Worker error: {}", err),
+            WorkerError::TooManyRestarts(restarts) => {
+                write!(f, "Workers were restarted {} times", restarts)
+            }
+        }
+    }
+}
+
+impl From<io::Error> for WorkerError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for WorkerError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+enum Connection {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Connection {
+    fn connect(address: &str) -> io::Result<Connection> {
+        match address.split_once(':') {
+            Some(("tcp", address)) => Ok(Connection::Tcp(TcpStream::connect(address)?)),
+            #[cfg(unix)]
+            Some(("unix", path)) => Ok(Connection::Unix(UnixStream::connect(path)?)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid worker address: {}", address),
+            )),
+        }
+    }
+
+    fn try_clone(&self) -> io::Result<Connection> {
+        match self {
+            Connection::Tcp(stream) => Ok(Connection::Tcp(stream.try_clone()?)),
+            #[cfg(unix)]
+            Connection::Unix(stream) => Ok(Connection::Unix(stream.try_clone()?)),
+        }
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.read(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Tcp(stream) => stream.write(buf),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Tcp(stream) => stream.flush(),
+            #[cfg(unix)]
+            Connection::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, tempdir::TempDir),
+}
+
+impl Listener {
+    fn bind(transport: WorkerTransport) -> io::Result<Listener> {
+        let listener = match transport {
+            WorkerTransport::Tcp => Listener::Tcp(TcpListener::bind("127.0.0.1:0")?),
+            #[cfg(unix)]
+            WorkerTransport::Unix => {
+                let dir = tempdir::TempDir::new("neat_gru_workers")?;
+                let listener = UnixListener::bind(dir.path().join("socket"))?;
+                Listener::Unix(listener, dir)
+            }
+        };
+        match &listener {
+            Listener::Tcp(listener) => listener.set_nonblocking(true)?,
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => listener.set_nonblocking(true)?,
+        }
+        Ok(listener)
+    }
+
+    fn address(&self) -> io::Result<String> {
+        match self {
+            Listener::Tcp(listener) => Ok(format!("tcp:{}", listener.local_addr()?)),
+            #[cfg(unix)]
+            Listener::Unix(_, dir) => {
+                let path: PathBuf = dir.path().join("socket");
+                Ok(format!("unix:{}", path.display()))
+            }
+        }
+    }
+
+    /// Waits for a worker to connect, fails early if it exits before connecting
+    fn accept(&self, child: &mut Child, timeout: Duration) -> io::Result<Connection> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let accepted = match self {
+                Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(false)?;
+                    Ok(Connection::Tcp(stream))
+                }),
+                #[cfg(unix)]
+                Listener::Unix(listener, _) => listener.accept().and_then(|(stream, _)| {
+                    stream.set_nonblocking(false)?;
+                    Ok(Connection::Unix(stream))
+                }),
+            };
+            match accepted {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if let Some(status) = child.try_wait()? {
+                        return Err(io::Error::new(
+                            io::ErrorKind::ConnectionRefused,
+                            format!("Worker exited before connecting: {}", status),
+                        ));
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(io::ErrorKind::TimedOut.into());
+                    }
+                    std::thread::sleep(Duration::from_millis(5));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Kills the worker process when dropped, unless it already exited
+struct ChildGuard(Child);
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if let Ok(None) = self.0.try_wait() {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+}
+
+struct Worker {
+    child: ChildGuard,
+    writer: Connection,
+    reader: BufReader<Connection>,
+}
+
+impl Worker {
+    fn evaluate<F>(&mut self, generation: usize, networks: &[Value]) -> Result<Vec<F>, WorkerError>
+    where
+        F: DeserializeOwned,
+    {
+        let request = EvaluatorRequest::Generation {
+            generation,
+            networks: Cow::Borrowed(networks),
+        };
+        serde_json::to_writer(&mut self.writer, &request)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(WorkerError::Io(io::ErrorKind::UnexpectedEof.into()));
+        }
+        let response: EvaluatorResponse<F> = serde_json::from_str(&line)?;
+        match response.error {
+            Some(error) => Err(WorkerError::Worker(error)),
+            None if response.fitness.len() != networks.len() => Err(WorkerError::Worker(format!(
+                "Expected {} results, got {}",
+                networks.len(),
+                response.fitness.len()
+            ))),
+            None => Ok(response.fitness),
+        }
+    }
+
+    /// Asks the worker to exit, it is killed if the request can't be sent or if it is still
+    /// running after `timeout`
+    fn shutdown(mut self, timeout: Duration) {
+        let request = EvaluatorRequest::End { best: None };
+        if serde_json::to_writer(&mut self.writer, &request).is_err()
+            || self.writer.write_all(b"\n").is_err()
+            || self.writer.flush().is_err()
+        {
+            return;
+        }
+        let deadline = Instant::now() + timeout;
+        while let Ok(None) = self.child.0.try_wait() {
+            if Instant::now() >= deadline {
+                // Killed when the guard is dropped
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+/// A game whose networks are evaluated by local worker processes
+///
+/// The workers are spawned when the first generation is evaluated
+pub struct WorkerPool<F> {
+    command: Command,
+    worker_count: usize,
+    transport: WorkerTransport,
+    timeout: Duration,
+    max_restarts: usize,
+    batch_size: Option<usize>,
+    listener: Option<Listener>,
+    workers: Vec<Worker>,
+    networks: Vec<Value>,
+    generation: usize,
+    restarts: usize,
+    _float: std::marker::PhantomData<F>,
+}
+
+impl<F> WorkerPool<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send + Sync + Serialize + DeserializeOwned,
+{
+    /// Default values are:
+    /// - transport -> Tcp
+    /// - timeout -> maximum time a worker can take to connect or to evaluate a batch: 60s
+    /// - max_restarts -> number of crashed or timed out workers after which the training stops: 10
+    /// - batch_size -> networks sent at once to a worker: the population divided by the worker count
+    ///
+    /// # Arguments
+    ///
+    /// `command` - The worker executable, it has to call `run_worker`
+    ///
+    /// `worker_count` - The number of worker processes
+    pub fn new(command: Command, worker_count: usize) -> WorkerPool<F> {
+        WorkerPool {
+            command,
+            worker_count: worker_count.max(1),
+            transport: WorkerTransport::Tcp,
+            timeout: Duration::from_secs(60),
+            max_restarts: 10,
+            batch_size: None,
+            listener: None,
+            workers: Vec::new(),
+            networks: Vec::new(),
+            generation: 0,
+            restarts: 0,
+            _float: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the socket used between the trainer and the workers
+    pub fn transport(mut self, transport: WorkerTransport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Sets the maximum time a worker can take to connect or to evaluate a batch
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of crashed or timed out workers after which the training stops
+    pub fn max_restarts(mut self, restarts: usize) -> Self {
+        self.max_restarts = restarts;
+        self
+    }
+
+    /// Sets the number of networks sent at once to a worker
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = Some(size.max(1));
+        self
+    }
+
+    /// Returns the number of workers restarted since the start of the training
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    fn spawn_worker(&mut self) -> Result<Worker, WorkerError> {
+        if self.listener.is_none() {
+            let listener = Listener::bind(self.transport)?;
+            self.command.env(WORKER_ADDRESS_VAR, listener.address()?);
+            self.listener = Some(listener);
+        }
+        let mut child = ChildGuard(self.command.spawn()?);
+        let connection = self
+            .listener
+            .as_ref()
+            .unwrap()
+            .accept(&mut child.0, self.timeout)?;
+        connection.set_read_timeout(Some(self.timeout))?;
+        Ok(Worker {
+            child,
+            reader: BufReader::new(connection.try_clone()?),
+            writer: connection,
+        })
+    }
+
+    /// Spawns workers until the pool is full, counting the restarts
+    fn fill(&mut self) -> Result<(), WorkerError> {
+        let initial = self.listener.is_none();
+        while self.workers.len() < self.worker_count {
+            if !initial {
+                self.restarts += 1;
+                log::warn!("RESTARTING WORKER ({} restarts)", self.restarts);
+                if self.restarts > self.max_restarts {
+                    return Err(WorkerError::TooManyRestarts(self.restarts - 1));
+                }
+            }
+            match self.spawn_worker() {
+                Ok(worker) => self.workers.push(worker),
+                Err(err) if initial => return Err(err),
+                Err(err) => log::warn!("Failed to restart worker: {}", err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Dispatches the batches to the workers, lost batches are given to other workers
+    fn evaluate(&mut self) -> Result<Vec<F>, WorkerError> {
+        let networks = std::mem::take(&mut self.networks);
+        let batch_size = self
+            .batch_size
+            .unwrap_or_else(|| networks.len().div_ceil(self.worker_count))
+            .max(1);
+        let pending = Mutex::new((0..networks.len()).step_by(batch_size).collect::<Vec<_>>());
+        let results: Mutex<Vec<Option<F>>> = Mutex::new(vec![None; networks.len()]);
+        let error: Mutex<Option<WorkerError>> = Mutex::new(None);
+        while !pending.lock().unwrap().is_empty() {
+            self.fill()?;
+            let generation = self.generation;
+            let workers = std::mem::take(&mut self.workers);
+            let survivors: Vec<Worker> = std::thread::scope(|scope| {
+                let handles: Vec<_> = workers
+                    .into_iter()
+                    .map(|mut worker| {
+                        let (pending, results, error, networks) =
+                            (&pending, &results, &error, &networks);
+                        scope.spawn(move || loop {
+                            let start = match pending.lock().unwrap().pop() {
+                                Some(start) => start,
+                                None => return Some(worker),
+                            };
+                            let end = (start + batch_size).min(networks.len());
+                            match worker.evaluate::<F>(generation, &networks[start..end]) {
+                                Ok(fitness) => {
+                                    let mut results = results.lock().unwrap();
+                                    for (index, value) in fitness.into_iter().enumerate() {
+                                        results[start + index] = Some(value);
+                                    }
+                                }
+                                Err(WorkerError::Worker(message)) => {
+                                    *error.lock().unwrap() = Some(WorkerError::Worker(message));
+                                    pending.lock().unwrap().clear();
+                                    return Some(worker);
+                                }
+                                Err(err) => {
+                                    log::warn!("WORKER LOST: {}", err);
+                                    pending.lock().unwrap().push(start);
+                                    // Dropping the worker kills its process
+                                    return None;
+                                }
+                            }
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .filter_map(|handle| handle.join().ok().flatten())
+                    .collect()
+            });
+            self.workers = survivors;
+            if let Some(err) = error.lock().unwrap().take() {
+                return Err(err);
+            }
+        }
+        Ok(results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|fitness| fitness.unwrap_or_else(F::nan))
+            .collect())
+    }
+
+    fn set_topologies(&mut self, topologies: &[TopologySmrtPtr<F>]) -> Result<(), WorkerError> {
+        self.networks = topologies
+            .iter()
+            .map(|topology| serde_json::from_str(&topology.lock().unwrap().to_string()))
+            .collect::<Result<Vec<Value>, serde_json::Error>>()?;
+        Ok(())
+    }
+
+    /// Stops the workers
+    fn end(&mut self, _history: Vec<HistoricTopologyLazy<F>>) -> Result<(), WorkerError> {
+        for worker in self.workers.drain(..) {
+            worker.shutdown(self.timeout);
+        }
+        Ok(())
+    }
+}
+
+impl_try_game!(WorkerPool, WorkerError);
+
+/// Runs a worker of a `WorkerPool`, evaluating the networks with `game` until the training ends
+///
+/// Has to be called by the worker executable, the address of the trainer is read from the
+/// `NEAT_GRU_WORKER_ADDRESS` environment variable
+///
+/// # Arguments
+///
+/// `game` - The game evaluating the networks
+pub fn run_worker<F, G>(game: &mut G) -> Result<(), WorkerError>
+where
+    F: Float + std::ops::AddAssign + Display + Send + Serialize,
+    G: Game<F>,
+{
+    let address = std::env::var(WORKER_ADDRESS_VAR).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is not set", WORKER_ADDRESS_VAR),
+        )
+    })?;
+    let connection = Connection::connect(&address)?;
+    let reader = BufReader::new(connection.try_clone()?);
+    serve(game, reader, connection).map_err(|err| WorkerError::Worker(err.to_string()))
+}
@@ -1,9 +1,35 @@
 use crate::neural_network::NeuralNetwork;
+use crate::train::hall_of_fame::HallOfFameMember;
+use crate::train::pareto::ParetoMember;
 use crate::train::HistoricTopologyLazy;
 use async_trait::async_trait;
 use num::Float;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::fmt::Display;
 
+/// Identity of a network given to `reset_players_with_info`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkInfo {
+    /// Id of the genome, the same network keeps it across generations
+    pub id: u64,
+    /// Id of the species of the network
+    pub species_id: usize,
+    /// Generation in which the network was first evaluated
+    pub birth_generation: usize,
+    /// Ids of the genomes the network was mutated or crossed over from, empty for the first
+    /// generation
+    pub parents: Vec<u64>,
+    /// Number of genes of the network
+    pub genome_size: usize,
+    /// Generation in which the network is evaluated, see `Train::generation`
+    pub generation: usize,
+    /// Index of the evaluation in the generation: 0 when the whole generation is evaluated, then
+    /// 1, 2, ... when noise handling or local search evaluates some networks again. The networks
+    /// evaluated again by noise handling keep their `id`
+    pub evaluation: usize,
+}
+
 /// Trait to implement in order to use Train
 pub trait Game<T>
 where
@@ -19,12 +45,177 @@ where
     /// `nets` - A vector containing the last generation of neural networks
     fn reset_players(&mut self, nets: Vec<NeuralNetwork<T>>);
 
+    /// Same as `reset_players` with the identity of every network, e.g. to group the networks by
+    /// species or to reuse the evaluation of a network that didn't change
+    ///
+    /// Calls `reset_players` by default, which isn't called by `Train` when this is overridden
+    ///
+    /// # Arguments
+    ///
+    /// `players` - The networks of the last generation and their identity
+    fn reset_players_with_info(&mut self, players: Vec<(NetworkInfo, NeuralNetwork<T>)>) {
+        self.reset_players(players.into_iter().map(|(_, net)| net).collect());
+    }
+
+    /// Behaviour descriptors of the last generation, used by novelty search
+    ///
+    /// Only called after `run_generation` when novelty search is enabled, must return one
+    /// descriptor per network, in the same order as the fitness
+    fn behaviour_descriptors(&mut self) -> Vec<Vec<T>> {
+        Vec::new()
+    }
+
+    /// Receives the hall of fame before every generation when it is enabled, called before
+    /// `reset_players`, e.g. to play against the best genomes so far
+    ///
+    /// # Arguments
+    ///
+    /// `hall_of_fame` - The best genomes of the training so far, the best first
+    fn reset_hall_of_fame(&mut self, _hall_of_fame: &[HallOfFameMember<T>]) {}
+
     /// Function to be run at the end of the training
     ///
     /// # Arguments
     ///
     /// `net` - The best historical network
     fn post_training(&mut self, history: Vec<HistoricTopologyLazy<T>>);
+
+    /// Function to be run at the end of the training, after `post_training`, when the hall of
+    /// fame is enabled
+    ///
+    /// # Arguments
+    ///
+    /// `hall_of_fame` - The best genomes of the training, the best first
+    fn post_training_hall_of_fame(&mut self, _hall_of_fame: Vec<HallOfFameMember<T>>) {}
+}
+
+/// Fallible version of `Game`, the errors stop the training and are returned by `Train::start`
+/// as a `TrainingError::Game`
+///
+/// Implemented for every `Game` with `Infallible` errors
+pub trait TryGame<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Run a game round
+    fn try_run_generation(&mut self) -> Result<Vec<T>, Self::Error>;
+
+    /// Resets the neural networks
+    ///
+    /// # Arguments
+    ///
+    /// `nets` - A vector containing the last generation of neural networks
+    fn try_reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) -> Result<(), Self::Error>;
+
+    /// Same as `try_reset_players` with the identity of every network
+    ///
+    /// Calls `try_reset_players` by default
+    ///
+    /// # Arguments
+    ///
+    /// `players` - The networks of the last generation and their identity
+    fn try_reset_players_with_info(
+        &mut self,
+        players: Vec<(NetworkInfo, NeuralNetwork<T>)>,
+    ) -> Result<(), Self::Error> {
+        self.try_reset_players(players.into_iter().map(|(_, net)| net).collect())
+    }
+
+    /// Behaviour descriptors of the last generation, used by novelty search
+    fn try_behaviour_descriptors(&mut self) -> Result<Vec<Vec<T>>, Self::Error> {
+        Ok(Vec::new())
+    }
+
+    /// Receives the hall of fame before every generation when it is enabled, called before
+    /// `try_reset_players`
+    ///
+    /// # Arguments
+    ///
+    /// `hall_of_fame` - The best genomes of the training so far, the best first
+    fn try_reset_hall_of_fame(
+        &mut self,
+        _hall_of_fame: &[HallOfFameMember<T>],
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Function to be run at the end of the training
+    ///
+    /// # Arguments
+    ///
+    /// `history` - The best topologies of every generation
+    fn try_post_training(
+        &mut self,
+        history: Vec<HistoricTopologyLazy<T>>,
+    ) -> Result<(), Self::Error>;
+
+    /// Function to be run at the end of the training, after `try_post_training`, when the hall
+    /// of fame is enabled
+    ///
+    /// # Arguments
+    ///
+    /// `hall_of_fame` - The best genomes of the training, the best first
+    fn try_post_training_hall_of_fame(
+        &mut self,
+        _hall_of_fame: Vec<HallOfFameMember<T>>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<T, G> TryGame<T> for G
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+    G: Game<T>,
+{
+    type Error = Infallible;
+
+    fn try_run_generation(&mut self) -> Result<Vec<T>, Infallible> {
+        Ok(self.run_generation())
+    }
+
+    fn try_reset_players(&mut self, nets: Vec<NeuralNetwork<T>>) -> Result<(), Infallible> {
+        self.reset_players(nets);
+        Ok(())
+    }
+
+    fn try_reset_players_with_info(
+        &mut self,
+        players: Vec<(NetworkInfo, NeuralNetwork<T>)>,
+    ) -> Result<(), Infallible> {
+        self.reset_players_with_info(players);
+        Ok(())
+    }
+
+    fn try_behaviour_descriptors(&mut self) -> Result<Vec<Vec<T>>, Infallible> {
+        Ok(self.behaviour_descriptors())
+    }
+
+    fn try_reset_hall_of_fame(
+        &mut self,
+        hall_of_fame: &[HallOfFameMember<T>],
+    ) -> Result<(), Infallible> {
+        self.reset_hall_of_fame(hall_of_fame);
+        Ok(())
+    }
+
+    fn try_post_training(
+        &mut self,
+        history: Vec<HistoricTopologyLazy<T>>,
+    ) -> Result<(), Infallible> {
+        self.post_training(history);
+        Ok(())
+    }
+
+    fn try_post_training_hall_of_fame(
+        &mut self,
+        hall_of_fame: Vec<HallOfFameMember<T>>,
+    ) -> Result<(), Infallible> {
+        self.post_training_hall_of_fame(hall_of_fame);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -34,3 +225,21 @@ where
 {
     async fn run_generation_async(&mut self) -> Vec<T>;
 }
+
+/// Trait to implement in order to train on several objectives at once with `start_multi_objective`
+///
+/// `run_generation` is not called during a multi-objective training
+pub trait MultiObjectiveGame<T>: Game<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Run a game round, returns the objectives of every network, all objectives are maximised
+    fn run_generation_objectives(&mut self) -> Vec<Vec<T>>;
+
+    /// Function to be run at the end of the training, after `post_training`
+    ///
+    /// # Arguments
+    ///
+    /// `front` - The Pareto front of the last generation
+    fn post_training_pareto(&mut self, _front: Vec<ParetoMember<T>>) {}
+}
@@ -1,3 +1,31 @@
+macro_rules! cond_iter {
+    ($collection: expr) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            $collection.par_iter()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            $collection.iter()
+        }
+    }};
+}
+
+macro_rules! cond_iter_mut {
+    ($collection: expr) => {{
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            $collection.par_iter_mut()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            $collection.iter_mut()
+        }
+    }};
+}
+
+pub mod benchmarks;
+pub mod evaluation;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod ffi;
 pub mod game;
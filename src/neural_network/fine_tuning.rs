@@ -0,0 +1,784 @@
+//! Fine-tuning of the weights of an evolved topology with backpropagation through time
+//!
+//! The gradients follow the exact forward equations of `NeuralNetwork::compute`, including
+//! `fast_sigmoid` and `fast_tanh`, so the tuned weights behave the same once written back into
+//! the `Topology`
+use crate::neural_network::functions::{
+    fast_sigmoid, fast_sigmoid_derivative, fast_tanh, fast_tanh_derivative,
+};
+use crate::topology::bias::Bias;
+use crate::topology::connection_type::ConnectionType;
+use crate::topology::gene::Point;
+use crate::topology::Topology;
+use crate::train::error::TrainingError;
+use num::Float;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// A sequence of inputs given to the network one after the other, from a reset state, and the
+/// expected outputs after each input
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sequence<T> {
+    pub inputs: Vec<Vec<T>>,
+    pub targets: Vec<Vec<T>>,
+}
+
+impl<T> Sequence<T> {
+    pub fn new(inputs: Vec<Vec<T>>, targets: Vec<Vec<T>>) -> Sequence<T> {
+        Sequence { inputs, targets }
+    }
+}
+
+/// Updates the weights from their gradients
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Optimizer {
+    Sgd {
+        learning_rate: f64,
+        momentum: f64,
+    },
+    Adam {
+        learning_rate: f64,
+        beta1: f64,
+        beta2: f64,
+        epsilon: f64,
+    },
+}
+
+impl Optimizer {
+    /// Stochastic gradient descent without momentum
+    pub fn sgd(learning_rate: f64) -> Optimizer {
+        Optimizer::Sgd {
+            learning_rate,
+            momentum: 0.0,
+        }
+    }
+
+    /// Adam with the usual betas, 0.9 and 0.999
+    pub fn adam(learning_rate: f64) -> Optimizer {
+        Optimizer::Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            epsilon: 1e-8,
+        }
+    }
+}
+
+/// State of the optimizer for every parameter
+struct OptimizerState {
+    step: i32,
+    first_moment: Vec<f64>,
+    second_moment: Vec<f64>,
+}
+
+impl OptimizerState {
+    fn new(parameters: usize) -> OptimizerState {
+        OptimizerState {
+            step: 0,
+            first_moment: vec![0.0; parameters],
+            second_moment: vec![0.0; parameters],
+        }
+    }
+
+    fn update<T: Float>(&mut self, optimizer: &Optimizer, parameters: &mut [T], gradients: &[T]) {
+        self.step += 1;
+        let moments = self.first_moment.iter_mut().zip(&mut self.second_moment);
+        let updates = parameters.iter_mut().zip(gradients).zip(moments);
+        for ((parameter, gradient), (first, second)) in updates {
+            let gradient = gradient.to_f64().unwrap_or(0.0);
+            let delta = match *optimizer {
+                Optimizer::Sgd {
+                    learning_rate,
+                    momentum,
+                } => {
+                    *first = momentum * *first + gradient;
+                    learning_rate * *first
+                }
+                Optimizer::Adam {
+                    learning_rate,
+                    beta1,
+                    beta2,
+                    epsilon,
+                } => {
+                    *first = beta1 * *first + (1.0 - beta1) * gradient;
+                    *second = beta2 * *second + (1.0 - beta2) * gradient * gradient;
+                    let first = *first / (1.0 - beta1.powi(self.step));
+                    let second = *second / (1.0 - beta2.powi(self.step));
+                    learning_rate * first / (second.sqrt() + epsilon)
+                }
+            };
+            *parameter = *parameter - T::from(delta).unwrap();
+        }
+    }
+}
+
+/// Tunes the weights and biases of a topology on a dataset of sequences, minimizing the mean
+/// squared error of the outputs
+///
+/// ```
+/// use neat_gru::neural_network::fine_tuning::{FineTuner, Optimizer, Sequence};
+/// use neat_gru::topology::Topology;
+///
+/// # fn tune(topology: &mut Topology<f64>) -> Result<(), neat_gru::train::error::TrainingError> {
+/// let dataset = vec![Sequence::new(
+///     vec![vec![0.0, 1.0], vec![1.0, 0.0]],
+///     vec![vec![0.5], vec![-0.5]],
+/// )];
+/// let losses = FineTuner::new(Optimizer::adam(0.01))
+///     .epochs(100)
+///     .fit(topology, &dataset)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct FineTuner {
+    optimizer: Optimizer,
+    epochs: usize,
+    batch_size: usize,
+}
+
+impl FineTuner {
+    /// Default values are:
+    /// - epochs -> 10
+    /// - batch_size -> 1
+    ///
+    /// # Arguments
+    ///
+    /// `optimizer` - SGD or Adam
+    pub fn new(optimizer: Optimizer) -> FineTuner {
+        FineTuner {
+            optimizer,
+            epochs: 10,
+            batch_size: 1,
+        }
+    }
+
+    /// Sets the number of passes over the dataset
+    pub fn epochs(mut self, epochs: usize) -> Self {
+        self.epochs = epochs;
+        self
+    }
+
+    /// Sets the number of sequences whose gradients are averaged for each update
+    ///
+    /// # Arguments
+    ///
+    /// `batch_size` - Number of sequences, at least 1
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Trains the weights and writes them back into the topology, returns the loss of every
+    /// epoch, computed before the updates of the epoch
+    ///
+    /// May return an InvalidDataset Error if the dataset doesn't match the topology
+    ///
+    /// # Arguments
+    ///
+    /// `topology` - The topology to tune, its structure is unchanged
+    ///
+    /// `dataset` - The sequences to learn
+    pub fn fit<T>(
+        &self,
+        topology: &mut Topology<T>,
+        dataset: &[Sequence<T>],
+    ) -> Result<Vec<T>, TrainingError>
+    where
+        T: Float + std::ops::AddAssign + Display + Send,
+    {
+        let graph = Graph::new(topology);
+        graph.check_dataset(dataset)?;
+        let mut parameters = parameters(topology);
+        let mut state = OptimizerState::new(parameters.len());
+        let mut gradients = vec![T::zero(); parameters.len()];
+        let mut losses = Vec::with_capacity(self.epochs);
+        for _ in 0..self.epochs {
+            let mut epoch_loss = T::zero();
+            for batch in dataset.chunks(self.batch_size) {
+                gradients
+                    .iter_mut()
+                    .for_each(|gradient| *gradient = T::zero());
+                for sequence in batch {
+                    epoch_loss += graph.backpropagate(&parameters, sequence, &mut gradients);
+                }
+                let scale = T::from(batch.len()).unwrap();
+                gradients
+                    .iter_mut()
+                    .for_each(|gradient| *gradient = *gradient / scale);
+                state.update(&self.optimizer, &mut parameters, &gradients);
+            }
+            losses.push(epoch_loss / T::from(dataset.len().max(1)).unwrap());
+        }
+        set_parameters(topology, &parameters);
+        Ok(losses)
+    }
+}
+
+/// Returns the mean squared error of the topology on the dataset, and its gradient with respect
+/// to every parameter, in the order of `parameters`
+///
+/// May return an InvalidDataset Error if the dataset doesn't match the topology
+pub fn loss_and_gradients<T>(
+    topology: &Topology<T>,
+    dataset: &[Sequence<T>],
+) -> Result<(T, Vec<T>), TrainingError>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    let graph = Graph::new(topology);
+    graph.check_dataset(dataset)?;
+    let parameters = parameters(topology);
+    let mut gradients = vec![T::zero(); parameters.len()];
+    let mut loss = T::zero();
+    for sequence in dataset {
+        loss += graph.backpropagate(&parameters, sequence, &mut gradients);
+    }
+    let count = T::from(dataset.len().max(1)).unwrap();
+    gradients
+        .iter_mut()
+        .for_each(|gradient| *gradient = *gradient / count);
+    Ok((loss / count, gradients))
+}
+
+/// Returns the weights of the enabled genes and the biases used by the network built from the
+/// topology: for every neuron with enabled genes, its bias then the weights of its genes, then
+/// the biases of the outputs
+pub fn parameters<T>(topology: &Topology<T>) -> Vec<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    let mut parameters = Vec::new();
+    let bias_values = |bias: &Bias<T>| [bias.bias_input, bias.bias_update, bias.bias_reset];
+    for point in trainable_points(topology) {
+        let bias_and_genes = &topology.genes_point[&point];
+        parameters.extend(bias_values(&bias_and_genes.bias));
+        for gene in &bias_and_genes.genes {
+            let gene = gene.borrow();
+            if gene.disabled {
+                continue;
+            }
+            parameters.push(gene.input_weight);
+            if let ConnectionType::GRU = gene.connection_type {
+                parameters.extend([
+                    gene.memory_weight,
+                    gene.reset_input_weight,
+                    gene.update_input_weight,
+                    gene.reset_memory_weight,
+                    gene.update_memory_weight,
+                ]);
+            }
+        }
+    }
+    for bias in &topology.output_bias {
+        parameters.extend(bias_values(bias));
+    }
+    parameters
+}
+
+/// Writes parameters returned by `parameters` back into the topology
+///
+/// # Arguments
+///
+/// `values` - One value per parameter, in the order of `parameters`
+pub fn set_parameters<T>(topology: &mut Topology<T>, values: &[T])
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    let mut values = values.iter().copied();
+    let mut set = |slot: &mut T| {
+        if let Some(value) = values.next() {
+            *slot = value;
+        }
+    };
+    let set_bias = |bias: &mut Bias<T>, set: &mut dyn FnMut(&mut T)| {
+        set(&mut bias.bias_input);
+        set(&mut bias.bias_update);
+        set(&mut bias.bias_reset);
+    };
+    for point in trainable_points(topology) {
+        let bias_and_genes = topology.genes_point.get_mut(&point).unwrap();
+        set_bias(&mut bias_and_genes.bias, &mut set);
+        for gene in &bias_and_genes.genes {
+            let gene = &mut *gene.borrow_mut();
+            if gene.disabled {
+                continue;
+            }
+            set(&mut gene.input_weight);
+            if let ConnectionType::GRU = gene.connection_type {
+                set(&mut gene.memory_weight);
+                set(&mut gene.reset_input_weight);
+                set(&mut gene.update_input_weight);
+                set(&mut gene.reset_memory_weight);
+                set(&mut gene.update_memory_weight);
+            }
+        }
+    }
+    for bias in &mut topology.output_bias {
+        set_bias(bias, &mut set);
+    }
+}
+
+/// Returns the neurons whose bias and genes are used by the network, sorted by layer and index
+fn trainable_points<T>(topology: &Topology<T>) -> Vec<Point>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    let output_layer = topology.layers_sizes.len().saturating_sub(1) as u8;
+    let mut points: Vec<Point> = topology
+        .genes_point
+        .iter()
+        .filter(|(point, bias_and_genes)| {
+            point.layer != output_layer
+                && bias_and_genes
+                    .genes
+                    .iter()
+                    .any(|gene| !gene.borrow().disabled)
+        })
+        .map(|(point, _)| *point)
+        .collect();
+    points.sort_by_key(|point| (point.layer, point.index));
+    points
+}
+
+/// Index of the first neuron of every layer, as in `NeuralNetwork::new`
+fn layer_addresses(layers_sizes: &[u8]) -> Vec<usize> {
+    layers_sizes
+        .iter()
+        .scan(0, |address, &size| {
+            let current = *address;
+            *address += size as usize;
+            Some(current)
+        })
+        .collect()
+}
+
+/// A connection of the network, with the indices of its weights among the parameters
+struct Link {
+    connection_type: ConnectionType,
+    from: usize,
+    to: usize,
+    /// Input, memory, reset input, update input, reset memory and update memory weights, only
+    /// the first one is used by sigmoid and relu connections
+    weights: [usize; 6],
+}
+
+/// The network built from a topology, with the parameters kept apart to be differentiated
+struct Graph {
+    inputs: usize,
+    outputs: usize,
+    neurons: usize,
+    /// Indices of the input, update and reset biases of every neuron, None for a zero bias
+    biases: Vec<Option<[usize; 3]>>,
+    /// Sorted by input neuron
+    links: Vec<Link>,
+}
+
+/// Gradients with respect to the state a GRU connection gives from one step to the next one
+struct LinkGradients<'g, T> {
+    /// Gradients of the memory and the previous input given to the next step
+    next_memory: &'g [T],
+    next_prev_input: &'g [T],
+    /// Gradients of the state given by the previous step, computed by the current step
+    memory: &'g mut [T],
+    prev_input: &'g mut [T],
+    prev_reset: &'g mut [T],
+}
+
+/// Values of a neuron computed during one step
+#[derive(Clone, Copy)]
+struct NeuronTape<T> {
+    input: T,
+    memory: T,
+    update: T,
+    reset: T,
+    /// Value given to the connections, or the value before the last `fast_tanh` for outputs
+    value: T,
+    /// Input of the `fast_tanh` of the current memory of outputs
+    candidate: T,
+}
+
+/// Values of a GRU connection computed during one step
+#[derive(Clone, Copy)]
+struct LinkTape<T> {
+    /// Input of the `fast_tanh` of the memory
+    activation: T,
+    memory: T,
+}
+
+/// Everything computed while running a sequence, indexed by step
+struct Trace<T> {
+    neurons: Vec<Vec<NeuronTape<T>>>,
+    links: Vec<Vec<LinkTape<T>>>,
+    outputs: Vec<Vec<T>>,
+}
+
+impl Graph {
+    fn new<T>(topology: &Topology<T>) -> Graph
+    where
+        T: Float + std::ops::AddAssign + Display + Send,
+    {
+        let sizes = &topology.layers_sizes;
+        let addresses = layer_addresses(sizes);
+        let neurons = sizes.iter().map(|&size| size as usize).sum();
+        let mut biases = vec![None; neurons];
+        let mut links = Vec::new();
+        let mut next = 0;
+        let mut take = |count: usize| {
+            let first = next;
+            next += count;
+            first
+        };
+        for point in trainable_points(topology) {
+            let bias_and_genes = &topology.genes_point[&point];
+            let from = addresses[point.layer as usize] + point.index as usize;
+            let first = take(3);
+            biases[from] = Some([first, first + 1, first + 2]);
+            for gene in &bias_and_genes.genes {
+                let gene = gene.borrow();
+                if gene.disabled {
+                    continue;
+                }
+                let to = addresses[gene.output.layer as usize] + gene.output.index as usize;
+                let count = match gene.connection_type {
+                    ConnectionType::GRU => 6,
+                    _ => 1,
+                };
+                let first = take(count);
+                let mut weights = [first; 6];
+                for (offset, weight) in weights.iter_mut().enumerate().take(count) {
+                    *weight = first + offset;
+                }
+                links.push(Link {
+                    connection_type: gene.connection_type,
+                    from,
+                    to,
+                    weights,
+                });
+            }
+        }
+        let outputs = topology.output_bias.len();
+        for bias in biases.iter_mut().skip(neurons - outputs) {
+            let first = take(3);
+            *bias = Some([first, first + 1, first + 2]);
+        }
+        links.sort_by_key(|link| link.from);
+        Graph {
+            inputs: sizes.first().map_or(0, |&size| size as usize),
+            outputs,
+            neurons,
+            biases,
+            links,
+        }
+    }
+
+    fn check_dataset<T>(&self, dataset: &[Sequence<T>]) -> Result<(), TrainingError> {
+        let invalid = |reason: String| Err(TrainingError::InvalidDataset(reason));
+        for (index, sequence) in dataset.iter().enumerate() {
+            if sequence.inputs.len() != sequence.targets.len() {
+                return invalid(format!(
+                    "sequence {} has {} inputs but {} targets",
+                    index,
+                    sequence.inputs.len(),
+                    sequence.targets.len()
+                ));
+            }
+            if sequence
+                .inputs
+                .iter()
+                .any(|input| input.len() != self.inputs)
+            {
+                return invalid(format!(
+                    "every input of sequence {} must have {} values",
+                    index, self.inputs
+                ));
+            }
+            if sequence
+                .targets
+                .iter()
+                .any(|target| target.len() != self.outputs)
+            {
+                return invalid(format!(
+                    "every target of sequence {} must have {} values",
+                    index, self.outputs
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_output(&self, neuron: usize) -> bool {
+        neuron >= self.neurons - self.outputs
+    }
+
+    /// Runs the sequence like `NeuralNetwork::compute`, keeping every intermediate value
+    fn forward<T: Float + std::ops::AddAssign>(
+        &self,
+        parameters: &[T],
+        sequence: &Sequence<T>,
+    ) -> Trace<T> {
+        let one = T::one();
+        let mut neuron_tapes = Vec::with_capacity(sequence.inputs.len());
+        let mut link_tapes: Vec<Vec<LinkTape<T>>> = Vec::with_capacity(sequence.inputs.len());
+        let mut outputs = Vec::with_capacity(sequence.inputs.len());
+        // State kept from one step to the other
+        let mut prev_reset = vec![T::zero(); self.neurons];
+        let mut link_memory = vec![T::zero(); self.links.len()];
+        let mut link_prev_input = vec![T::zero(); self.links.len()];
+        for inputs in &sequence.inputs {
+            let mut tape: Vec<NeuronTape<T>> = (0..self.neurons)
+                .map(|neuron| {
+                    let bias = |offset: usize| {
+                        self.biases[neuron].map_or(T::zero(), |bias| parameters[bias[offset]])
+                    };
+                    NeuronTape {
+                        input: bias(0),
+                        memory: T::zero(),
+                        update: bias(1),
+                        reset: bias(2),
+                        value: T::zero(),
+                        candidate: T::zero(),
+                    }
+                })
+                .collect();
+            for (neuron, &input) in inputs.iter().enumerate() {
+                let minus_thousand = T::from(-1000).unwrap();
+                tape[neuron].input = input;
+                tape[neuron].update = minus_thousand;
+                tape[neuron].reset = minus_thousand;
+                tape[neuron].memory = T::zero();
+            }
+            let mut link_tape = vec![
+                LinkTape {
+                    activation: T::zero(),
+                    memory: T::zero(),
+                };
+                self.links.len()
+            ];
+            let mut output = Vec::with_capacity(self.outputs);
+            let mut link = 0;
+            for neuron in 0..self.neurons {
+                let current = tape[neuron];
+                let update_gate = fast_sigmoid(current.update);
+                let reset_gate = fast_sigmoid(current.reset);
+                if self.is_output(neuron) {
+                    let candidate = current.input + current.memory * reset_gate;
+                    let value =
+                        update_gate * current.memory + (one - update_gate) * fast_tanh(candidate);
+                    tape[neuron].candidate = candidate;
+                    tape[neuron].value = value;
+                    prev_reset[neuron] = reset_gate;
+                    output.push(fast_tanh(value));
+                    continue;
+                }
+                let value = update_gate * current.memory
+                    + (one - update_gate) * (current.input + current.memory * reset_gate);
+                tape[neuron].value = value;
+                while link < self.links.len() && self.links[link].from == neuron {
+                    let Link {
+                        connection_type,
+                        to,
+                        weights: w,
+                        ..
+                    } = self.links[link];
+                    match connection_type {
+                        ConnectionType::Sigmoid => tape[to].input += value * parameters[w[0]],
+                        ConnectionType::Relu => {
+                            tape[to].input += (value * parameters[w[0]]).max(T::zero())
+                        }
+                        ConnectionType::GRU => {
+                            let activation = link_prev_input[link] * parameters[w[0]]
+                                + parameters[w[1]] * prev_reset[to] * link_memory[link];
+                            let memory = fast_tanh(activation);
+                            link_tape[link] = LinkTape { activation, memory };
+                            link_memory[link] = memory;
+                            link_prev_input[link] = value;
+                            let target = &mut tape[to];
+                            target.memory += memory * parameters[w[1]];
+                            target.input += value * parameters[w[0]];
+                            target.reset += value * parameters[w[2]] + memory * parameters[w[4]];
+                            target.update += value * parameters[w[3]] + memory * parameters[w[5]];
+                        }
+                    }
+                    link += 1;
+                }
+                prev_reset[neuron] = reset_gate;
+            }
+            neuron_tapes.push(tape);
+            link_tapes.push(link_tape);
+            outputs.push(output);
+        }
+        Trace {
+            neurons: neuron_tapes,
+            links: link_tapes,
+            outputs,
+        }
+    }
+
+    /// Adds the gradient of the mean squared error of the sequence to `gradients`, returns the
+    /// error
+    fn backpropagate<T: Float + std::ops::AddAssign>(
+        &self,
+        parameters: &[T],
+        sequence: &Sequence<T>,
+        gradients: &mut [T],
+    ) -> T {
+        let Trace {
+            neurons: neuron_tapes,
+            links: link_tapes,
+            outputs,
+        } = self.forward(parameters, sequence);
+        let steps = sequence.inputs.len();
+        if steps == 0 || self.outputs == 0 {
+            return T::zero();
+        }
+        let one = T::one();
+        let two = one + one;
+        let count = T::from(steps * self.outputs).unwrap();
+        let mut loss = T::zero();
+        // Gradients with respect to the state given by a step to the next one
+        let mut next_prev_reset = vec![T::zero(); self.neurons];
+        let mut next_link_memory = vec![T::zero(); self.links.len()];
+        let mut next_link_prev_input = vec![T::zero(); self.links.len()];
+        for step in (0..steps).rev() {
+            let tape = &neuron_tapes[step];
+            let mut prev_reset_grad = vec![T::zero(); self.neurons];
+            let mut link_memory_grad = vec![T::zero(); self.links.len()];
+            let mut link_prev_input_grad = vec![T::zero(); self.links.len()];
+            // Gradients of the input, memory, update and reset sums of every neuron
+            let mut sums = vec![[T::zero(); 4]; self.neurons];
+            let mut value_grad = vec![T::zero(); self.neurons];
+            let mut link = self.links.len();
+            for neuron in (0..self.neurons).rev() {
+                let current = tape[neuron];
+                let update_gate = fast_sigmoid(current.update);
+                let reset_gate = fast_sigmoid(current.reset);
+                let (candidate, candidate_grad) = if self.is_output(neuron) {
+                    let index = neuron - (self.neurons - self.outputs);
+                    let error = outputs[step][index] - sequence.targets[step][index];
+                    loss += error * error;
+                    let output_grad = two * error / count;
+                    value_grad[neuron] = output_grad * fast_tanh_derivative(current.value);
+                    (
+                        fast_tanh(current.candidate),
+                        fast_tanh_derivative(current.candidate),
+                    )
+                } else {
+                    while link > 0 && self.links[link - 1].from == neuron {
+                        link -= 1;
+                        self.backpropagate_link(
+                            link,
+                            step,
+                            parameters,
+                            &neuron_tapes,
+                            &link_tapes,
+                            &sums,
+                            &mut value_grad,
+                            gradients,
+                            LinkGradients {
+                                next_memory: &next_link_memory,
+                                next_prev_input: &next_link_prev_input,
+                                memory: &mut link_memory_grad,
+                                prev_input: &mut link_prev_input_grad,
+                                prev_reset: &mut prev_reset_grad,
+                            },
+                        );
+                    }
+                    (current.input + current.memory * reset_gate, one)
+                };
+                let grad = value_grad[neuron];
+                let update_grad = grad * (current.memory - candidate);
+                let candidate_sum_grad = grad * (one - update_gate) * candidate_grad;
+                let memory_grad = grad * update_gate + candidate_sum_grad * reset_gate;
+                let reset_gate_grad = candidate_sum_grad * current.memory + next_prev_reset[neuron];
+                sums[neuron] = [
+                    candidate_sum_grad,
+                    memory_grad,
+                    update_grad * fast_sigmoid_derivative(current.update),
+                    reset_gate_grad * fast_sigmoid_derivative(current.reset),
+                ];
+                if neuron >= self.inputs || self.is_output(neuron) {
+                    if let Some(bias) = self.biases[neuron] {
+                        gradients[bias[0]] += sums[neuron][0];
+                        gradients[bias[1]] += sums[neuron][2];
+                        gradients[bias[2]] += sums[neuron][3];
+                    }
+                }
+            }
+            next_prev_reset = prev_reset_grad;
+            next_link_memory = link_memory_grad;
+            next_link_prev_input = link_prev_input_grad;
+        }
+        loss / count
+    }
+
+    /// Backpropagates through a connection during a step
+    #[allow(clippy::too_many_arguments)]
+    fn backpropagate_link<T: Float + std::ops::AddAssign>(
+        &self,
+        link: usize,
+        step: usize,
+        parameters: &[T],
+        neuron_tapes: &[Vec<NeuronTape<T>>],
+        link_tapes: &[Vec<LinkTape<T>>],
+        sums: &[[T; 4]],
+        value_grad: &mut [T],
+        gradients: &mut [T],
+        state: LinkGradients<T>,
+    ) {
+        let Link {
+            connection_type,
+            from,
+            to,
+            weights: w,
+        } = self.links[link];
+        let value = neuron_tapes[step][from].value;
+        let [input_grad, target_memory_grad, update_grad, reset_grad] = sums[to];
+        match connection_type {
+            ConnectionType::Sigmoid => {
+                value_grad[from] += input_grad * parameters[w[0]];
+                gradients[w[0]] += input_grad * value;
+            }
+            ConnectionType::Relu => {
+                // Derivative of 1 at 0, so that the new connections with a zero weight learn
+                if value * parameters[w[0]] >= T::zero() {
+                    value_grad[from] += input_grad * parameters[w[0]];
+                    gradients[w[0]] += input_grad * value;
+                }
+            }
+            ConnectionType::GRU => {
+                let LinkTape { activation, memory } = link_tapes[step][link];
+                let (prev_input, prev_memory, prev_reset) = if step == 0 {
+                    (T::zero(), T::zero(), T::zero())
+                } else {
+                    let previous = &neuron_tapes[step - 1];
+                    (
+                        previous[from].value,
+                        link_tapes[step - 1][link].memory,
+                        fast_sigmoid(previous[to].reset),
+                    )
+                };
+                let link_memory_grad = state.next_memory[link]
+                    + target_memory_grad * parameters[w[1]]
+                    + reset_grad * parameters[w[4]]
+                    + update_grad * parameters[w[5]];
+                value_grad[from] += input_grad * parameters[w[0]]
+                    + reset_grad * parameters[w[2]]
+                    + update_grad * parameters[w[3]]
+                    + state.next_prev_input[link];
+                gradients[w[0]] += input_grad * value;
+                gradients[w[1]] += target_memory_grad * memory;
+                gradients[w[2]] += reset_grad * value;
+                gradients[w[3]] += update_grad * value;
+                gradients[w[4]] += reset_grad * memory;
+                gradients[w[5]] += update_grad * memory;
+                let activation_grad = link_memory_grad * fast_tanh_derivative(activation);
+                gradients[w[0]] += activation_grad * prev_input;
+                gradients[w[1]] += activation_grad * prev_reset * prev_memory;
+                state.prev_input[link] = activation_grad * parameters[w[0]];
+                state.memory[link] = activation_grad * parameters[w[1]] * prev_reset;
+                state.prev_reset[to] += activation_grad * parameters[w[1]] * prev_memory;
+            }
+        }
+    }
+}
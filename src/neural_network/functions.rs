@@ -19,3 +19,26 @@ pub fn fast_tanh<T: Float>(x: T) -> T {
     let b = 135135 + x2 * (62370 + x2 * (3150 + x2 * 28));
     a / b
 }
+
+/// Derivative of `fast_sigmoid`, takes the pre-activation value, not the output
+#[replace_numeric_literals(T::from(literal).unwrap())]
+#[inline]
+pub fn fast_sigmoid_derivative<T: Float>(value: T) -> T {
+    let denominator = 1 + value.abs();
+    1 / (denominator * denominator)
+}
+
+/// Derivative of `fast_tanh`, 0 where it is clamped
+#[replace_numeric_literals(T::from(literal).unwrap())]
+#[inline]
+pub fn fast_tanh_derivative<T: Float>(x: T) -> T {
+    if x.abs() >= 4.97 {
+        return 0;
+    }
+    let x2 = x * x;
+    let a = x * (135135 + x2 * (17325 + x2 * (378 + x2)));
+    let b = 135135 + x2 * (62370 + x2 * (3150 + x2 * 28));
+    let da = 135135 + x2 * (51975 + x2 * (1890 + x2 * 7));
+    let db = x * (124740 + x2 * (12600 + x2 * 168));
+    (da * b - a * db) / (b * b)
+}
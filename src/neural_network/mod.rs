@@ -1,6 +1,7 @@
 mod connection_gru;
 mod connection_relu;
 mod connection_sigmoid;
+pub mod fine_tuning;
 mod functions;
 mod neuron;
 mod nn;
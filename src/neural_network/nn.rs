@@ -3,7 +3,7 @@ use crate::neural_network::connection_sigmoid::ConnectionSigmoid;
 use crate::neural_network::neuron::Neuron;
 use crate::topology::bias::Bias;
 use crate::topology::connection_type::ConnectionType;
-use crate::topology::Topology;
+use crate::topology::{Topology, TopologySmrtPtr};
 use num::Float;
 use std::fmt::Display;
 
@@ -17,6 +17,9 @@ where
     output_size: usize,
     neurons: Vec<Neuron<T>>,
     biases: Vec<Bias<T>>,
+    /// Topology the network was built from by `Train`, sent to other processes by the evaluators
+    /// of `crate::evaluation`
+    topology: Option<TopologySmrtPtr<T>>,
 }
 
 unsafe impl<T> Send for NeuralNetwork<T> where T: Float + std::ops::AddAssign + Display + Send {}
@@ -108,12 +111,29 @@ where
             output_size,
             neurons,
             biases,
+            topology: None,
         };
 
         net.reset_neurons_value();
         net
     }
 
+    /// Instantiates the network of a topology shared with `Train`, see `topology`
+    ///
+    /// # Safety
+    ///
+    /// Same as `new`
+    pub(crate) unsafe fn from_shared(topology: &TopologySmrtPtr<T>) -> NeuralNetwork<T> {
+        let mut net = NeuralNetwork::new(&topology.lock().unwrap());
+        net.topology = Some(topology.clone());
+        net
+    }
+
+    /// Returns the topology given to `from_shared`
+    pub(crate) fn topology(&self) -> Option<&TopologySmrtPtr<T>> {
+        self.topology.as_ref()
+    }
+
     #[inline]
     fn reset_neurons_value(&mut self) {
         for (neuron, bias) in self.neurons.iter_mut().zip(self.biases.iter()) {
@@ -210,6 +230,7 @@ where
                 .map(Neuron::clone_with_old_pointer)
                 .collect(),
             biases: self.biases.clone(),
+            topology: self.topology.clone(),
         };
         if !self.neurons.is_empty() {
             let original_address = &self.neurons[0] as *const Neuron<T>;
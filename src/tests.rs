@@ -1,10 +1,42 @@
+use crate::benchmarks::harness::Statistics;
+use crate::benchmarks::{self, Benchmark, Harness, Parity, PoleBalancing};
+use crate::evaluation::competitive::{
+    Coevolution, CompetitiveEvaluation, PairingSchedule, Population,
+};
+use crate::evaluation::dataset::{read_csv, read_json_lines, split, DatasetGame, Loss};
+use crate::evaluation::evaluator::{Aggregation, Evaluator, EvaluatorGame};
+#[cfg(unix)]
+use crate::evaluation::external::ExternalEvaluator;
+use crate::evaluation::workers::{run_worker, WorkerPool, WORKER_ADDRESS_VAR};
+use crate::game::NetworkInfo;
+use crate::game::{MultiObjectiveGame, TryGame};
+use crate::neural_network::fine_tuning::{
+    loss_and_gradients, parameters, set_parameters, FineTuner, Optimizer, Sequence,
+};
 use crate::neural_network::NeuralNetwork;
 use crate::topology::mutation_probabilities::MutationProbabilities;
-use crate::topology::Topology;
-use crate::train::{HistoricTopologyLazy, Train};
+use crate::topology::{MutationOperation, Topology};
+use crate::train::config::TrainConfig;
+use crate::train::error::TrainingError;
+use crate::train::evolution_number::EvNumber;
+use crate::train::hall_of_fame::{HallOfFame, HallOfFameMember};
+use crate::train::history::{
+    DirectoryHistory, HistorySink, KeepEveryNth, KeepImprovements, KeepLast, MemoryHistory,
+};
+use crate::train::islands::{Islands, MigrationTopology};
+use crate::train::local_search::{LocalSearch, LocalSearchMethod};
+use crate::train::noise::NoiseHandling;
+use crate::train::novelty::{NoveltyArchive, NoveltySearch, ARCHIVE_FILE};
+use crate::train::pareto::{non_dominated_sort, ParetoMember};
+use crate::train::speciation::{CompatibilityMetric, SpeciesRepresentative};
+use crate::train::{HistoricTopologyLazy, InvalidFitnessPolicy, OwnedTrain, Train, TrainingStatus};
 use crate::{game::Game, section};
 use rand::{thread_rng, Rng};
+use std::collections::HashSet;
 use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::Arc;
+use tempdir::TempDir;
 
 macro_rules! check_output {
     ($output: expr, $as_str: expr, $index: expr) => {
@@ -230,3 +262,1611 @@ pub fn test_train_memory() {
         .outputs(5);
     runner.start().unwrap();
 }
+
+struct LayerCountMetric {
+    calls: Arc<AtomicUsize>,
+}
+
+impl CompatibilityMetric<f64> for LayerCountMetric {
+    fn distance(&self, top1: &Topology<f64>, top2: &Topology<f64>) -> f64 {
+        self.calls.fetch_add(1, AtomicOrdering::Relaxed);
+        (top1.layers_sizes.len() as f64 - top2.layers_sizes.len() as f64).abs()
+    }
+}
+
+/// Every topology is too far from the others to share a species
+struct DistinctMetric;
+
+impl CompatibilityMetric<f64> for DistinctMetric {
+    fn distance(&self, _top1: &Topology<f64>, _top2: &Topology<f64>) -> f64 {
+        1.0
+    }
+}
+
+#[test]
+pub fn test_train_custom_speciation() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut game = TestGame::new();
+    let mut runner: Train<TestGame, f64> = Train::new(&mut game);
+    runner
+        .max_layers(5)
+        .max_per_layers(10)
+        .iterations(30)
+        .max_individuals(50)
+        .inputs(5)
+        .outputs(5)
+        .delta_threshold(0.5)
+        .compatibility_metric(Box::new(LayerCountMetric {
+            calls: calls.clone(),
+        }))
+        .species_representative(SpeciesRepresentative::PreviousGenerationRandom);
+    runner.start().unwrap();
+    assert!(calls.load(AtomicOrdering::Relaxed) > 0);
+
+    let mut game = TestGame::new();
+    let mut runner: Train<TestGame, f64> = Train::new(&mut game);
+    runner
+        .iterations(5)
+        .max_individuals(20)
+        .inputs(5)
+        .outputs(5)
+        .delta_threshold(0.5)
+        .compatibility_metric(Box::new(DistinctMetric));
+    for _ in 0..5 {
+        let report = runner.step().unwrap();
+        assert!(report.species_count > 1);
+        assert_eq!(report.species_count, report.population);
+    }
+}
+
+struct NoveltyGame {
+    nets: Vec<NeuralNetwork<f64>>,
+    behaviours: Vec<Vec<f64>>,
+}
+
+impl Game<f64> for NoveltyGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.behaviours = self
+            .nets
+            .iter_mut()
+            .map(|network| network.compute(&[0.5, -0.5]))
+            .collect();
+        self.behaviours.iter().map(|output| output[0]).collect()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.nets = nets;
+    }
+
+    fn behaviour_descriptors(&mut self) -> Vec<Vec<f64>> {
+        self.behaviours.clone()
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+#[test]
+pub fn test_train_novelty() {
+    let mut game = NoveltyGame {
+        nets: Vec::new(),
+        behaviours: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .iterations(30)
+        .max_individuals(30)
+        .inputs(2)
+        .outputs(2)
+        .novelty_search(NoveltySearch::new(5, 0.5).archive_threshold(0.01));
+    for _ in 0..5 {
+        let report = runner.step().unwrap();
+        // The progress is tracked on the raw fitness, not on the blended selection score
+        let best = runner
+            .simulation
+            .behaviours
+            .iter()
+            .fold(f64::NEG_INFINITY, |best, output| best.max(output[0]));
+        assert_eq!(report.best_score, best);
+    }
+    runner.start().unwrap();
+    assert!(!runner.novelty_archive().is_empty());
+}
+
+/// Returns one behaviour descriptor less than the number of networks
+struct MissingDescriptorGame(NoveltyGame);
+
+impl Game<f64> for MissingDescriptorGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.0.run_generation()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.0.reset_players(nets)
+    }
+
+    fn behaviour_descriptors(&mut self) -> Vec<Vec<f64>> {
+        let mut behaviours = self.0.behaviour_descriptors();
+        behaviours.pop();
+        behaviours
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+#[test]
+pub fn test_train_novelty_descriptor_mismatch() {
+    let mut game = MissingDescriptorGame(NoveltyGame {
+        nets: Vec::new(),
+        behaviours: Vec::new(),
+    });
+    let mut runner = Train::new(&mut game);
+    runner
+        .max_individuals(10)
+        .inputs(2)
+        .outputs(2)
+        .novelty_search(NoveltySearch::new(5, 0.5));
+    match runner.step() {
+        Err(TrainingError::DescriptorLengthMismatch { expected, got }) => {
+            assert_eq!(got + 1, expected)
+        }
+        other => panic!(
+            "unexpected result {:?}",
+            other.map(|report| report.generation)
+        ),
+    }
+}
+
+#[test]
+pub fn test_novelty_archive_saved() {
+    let dir = TempDir::new("novelty").unwrap();
+    let mut game = NoveltyGame {
+        nets: Vec::new(),
+        behaviours: Vec::new(),
+    };
+    let archive = {
+        let mut runner = Train::new(&mut game);
+        runner
+            .iterations(5)
+            .max_individuals(20)
+            .inputs(2)
+            .outputs(2)
+            .novelty_search(NoveltySearch::new(5, 0.5).archive_threshold(0.01))
+            .history_sink(DirectoryHistory::new(dir.path()).unwrap());
+        runner.start().unwrap();
+        runner.novelty_archive().clone()
+    };
+    assert!(!archive.is_empty());
+
+    let loaded: NoveltyArchive<f64> = NoveltyArchive::load(dir.path().join(ARCHIVE_FILE)).unwrap();
+    assert_eq!(loaded.len(), archive.len());
+    for (loaded, saved) in loaded.behaviours().iter().zip(archive.behaviours()) {
+        assert_eq!(loaded.len(), saved.len());
+        assert!(loaded.iter().zip(saved).all(|(a, b)| (a - b).abs() < 1e-12));
+    }
+    let mut runner = Train::new(&mut game);
+    runner.restore_novelty_archive(loaded.clone());
+    assert_eq!(*runner.novelty_archive(), loaded);
+}
+
+struct TwoObjectivesGame {
+    nets: Vec<NeuralNetwork<f64>>,
+    front: Vec<ParetoMember<f64>>,
+}
+
+impl Game<f64> for TwoObjectivesGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.run_generation_objectives()
+            .into_iter()
+            .map(|objectives| objectives[0])
+            .collect()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.nets = nets;
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+impl MultiObjectiveGame<f64> for TwoObjectivesGame {
+    fn run_generation_objectives(&mut self) -> Vec<Vec<f64>> {
+        self.nets
+            .iter_mut()
+            .map(|network| network.compute(&[1.0, -1.0]))
+            .collect()
+    }
+
+    fn post_training_pareto(&mut self, front: Vec<ParetoMember<f64>>) {
+        self.front = front;
+    }
+}
+
+#[test]
+pub fn test_train_multi_objective() {
+    let fronts = non_dominated_sort(&[vec![1., 0.], vec![0., 1.], vec![0., 0.], vec![-1., -1.]]);
+    assert_eq!(fronts, vec![vec![0, 1], vec![2], vec![3]]);
+
+    let mut game = TwoObjectivesGame {
+        nets: Vec::new(),
+        front: Vec::new(),
+    };
+    {
+        let mut runner = Train::new(&mut game);
+        runner
+            .iterations(30)
+            .max_individuals(30)
+            .inputs(2)
+            .outputs(2)
+            .complexity_objective(true);
+        runner.start_multi_objective().unwrap();
+        let best = runner
+            .simulation
+            .front
+            .iter()
+            .fold(f64::NEG_INFINITY, |best, member| {
+                best.max(member.objectives[0])
+            });
+        assert_eq!(runner.last_best_score(), best);
+    }
+    assert!(!game.front.is_empty());
+    assert!(game.front.iter().all(|member| member.objectives.len() == 3));
+}
+
+#[test]
+pub fn test_train_islands() {
+    let mut games = [TestGame::new(), TestGame::new(), TestGame::new()];
+    // The callbacks are called on the thread running the islands
+    let callback_calls = Arc::new(AtomicUsize::new(0));
+    let thread = std::thread::current().id();
+    let trains = games
+        .iter_mut()
+        .enumerate()
+        .map(|(index, game)| {
+            let mut train = Train::new(game);
+            train
+                .iterations(20 + index * 5)
+                .max_individuals(30)
+                .inputs(5)
+                .outputs(5)
+                .delta_threshold(1. + index as f64);
+            let calls = callback_calls.clone();
+            train.access_train_object(Box::new(move |_| {
+                assert_eq!(std::thread::current().id(), thread);
+                calls.fetch_add(1, AtomicOrdering::SeqCst);
+            }));
+            train
+        })
+        .collect();
+    let mut islands = Islands::new(trains);
+    islands
+        .migration_interval(5)
+        .migrants(3)
+        .migration_topology(MigrationTopology::FullyConnected);
+    islands.start().unwrap();
+    let statistics = islands.statistics();
+    assert!(statistics.len() <= 20 + 25 + 30);
+    // Called after every generation that didn't end with an extinction
+    let survived = statistics
+        .iter()
+        .filter(|stats| stats.species_count > 0)
+        .count();
+    assert_eq!(callback_calls.load(AtomicOrdering::SeqCst), survived);
+    for island in 0..3 {
+        assert!(statistics
+            .iter()
+            .any(|stats| stats.island == island && stats.generation == 0));
+    }
+    drop(islands);
+    // Every island received the merged history, the last topology is evaluated by post_training
+    assert!(games.iter().all(|game| game.nets.len() == 2));
+}
+
+#[test]
+pub fn test_train_migration() {
+    let mut source_game = TestGame::new();
+    let mut destination_game = TestGame::new();
+    let mut source = Train::new(&mut source_game);
+    source.max_individuals(20).inputs(5).outputs(5);
+    let mut destination = Train::new(&mut destination_game);
+    destination.max_individuals(20).inputs(5).outputs(5);
+    for _ in 0..3 {
+        source.step().unwrap();
+        destination.step().unwrap();
+    }
+    let migrants = source.emigrants(3);
+    assert!(!migrants.is_empty());
+    for migrant in &migrants {
+        assert_eq!(migrant.parents().len(), 1);
+        assert_ne!(migrant.id(), migrant.parents()[0]);
+        assert_eq!(migrant.operations(), &[MutationOperation::Migration]);
+    }
+    destination.immigrate(migrants).unwrap();
+    // The migrants replace the worst members instead of growing the population
+    assert!(destination.simulation.nets.len() <= 20);
+}
+
+struct Trainer {
+    train: OwnedTrain<TestGame, f64>,
+}
+
+fn assert_send<T: Send>() {}
+
+#[test]
+pub fn test_train_owned() {
+    assert_send::<OwnedTrain<TestGame, f64>>();
+    let mut train = Train::new_owned(TestGame::new());
+    let generations = Arc::new(AtomicUsize::new(0));
+    let calls = generations.clone();
+    train
+        .iterations(10)
+        .max_individuals(20)
+        .inputs(5)
+        .outputs(5)
+        .access_train_object(Box::new(move |_| {
+            calls.fetch_add(1, AtomicOrdering::SeqCst);
+        }));
+    let mut trainer = Trainer { train };
+    // The train and its callback are moved to another thread
+    let handle = std::thread::spawn(move || {
+        trainer.train.start().unwrap();
+        trainer.train.into_simulation()
+    });
+    let game = handle.join().unwrap();
+    assert!(!game.nets.is_empty());
+    assert!(generations.load(AtomicOrdering::SeqCst) > 0);
+}
+
+#[test]
+pub fn test_train_step_by_step() {
+    let mut game = TestGame::new();
+    let mut runner = Train::new(&mut game);
+    runner.max_individuals(30).inputs(5).outputs(5);
+    runner.init().unwrap();
+    for generation in 0..10 {
+        let report = runner.step().unwrap();
+        assert_eq!(report.generation, generation);
+        assert!(report.extinct || report.population > 0);
+        runner.delta_threshold(1. + generation as f64 / 10.);
+    }
+    let population = runner.step().unwrap().population;
+    let report = runner.step_with_results(vec![0.; population]).unwrap();
+    assert_eq!(report.generation, 11);
+    assert_eq!(runner.generation(), 12);
+    runner.finish().unwrap();
+}
+
+#[test]
+pub fn test_train_stop_handle() {
+    let mut game = TestGame::new();
+    let mut runner = Train::new(&mut game);
+    let stop_handle = runner.stop_handle();
+    runner
+        .max_individuals(30)
+        .inputs(5)
+        .outputs(5)
+        .iterations(50)
+        .access_train_object(Box::new(move |train| {
+            if train.generation() == 3 {
+                stop_handle.stop();
+            }
+        }));
+    let outcome = runner.start().unwrap();
+    assert!(outcome.is_cancelled());
+    assert_eq!(outcome.generations, 3);
+    assert!(runner.stop_handle().is_stopped());
+    // A new training clears the stop request
+    let outcome = runner.iterations(2).start().unwrap();
+    assert_eq!(outcome.status, TrainingStatus::Completed);
+    assert!(!runner.stop_handle().is_stopped());
+}
+
+#[test]
+pub fn test_train_invalid_fitness() {
+    let mut game = TestGame::new();
+    let mut runner = Train::new(&mut game);
+    runner.max_individuals(30).inputs(5).outputs(5);
+    let population = runner.step().unwrap().population;
+    let mut results = vec![1.; population];
+    results[0] = f64::NAN;
+    results[1] = f64::INFINITY;
+    let population = runner
+        .step_with_results(results.clone())
+        .unwrap()
+        .population;
+    assert!(matches!(
+        runner.step_with_results(vec![1.; population + 1]),
+        Err(TrainingError::FitnessLengthMismatch { .. })
+    ));
+    runner.invalid_fitness_policy(InvalidFitnessPolicy::Drop);
+    results.resize(population, 1.);
+    let population = runner
+        .step_with_results(results.clone())
+        .unwrap()
+        .population;
+    // Given results can't be evaluated again, they are treated as the minimum
+    runner.invalid_fitness_policy(InvalidFitnessPolicy::Retry(3));
+    results.resize(population, 1.);
+    let report = runner.step_with_results(results.clone()).unwrap();
+    assert_eq!(report.best_score, 1.);
+    let population = report.population;
+    runner.invalid_fitness_policy(InvalidFitnessPolicy::Error);
+    results.resize(population, 1.);
+    assert!(matches!(
+        runner.step_with_results(results),
+        Err(TrainingError::InvalidFitness { index: 0, .. })
+    ));
+}
+
+#[derive(Debug)]
+struct SimulatorCrashed;
+
+impl std::fmt::Display for SimulatorCrashed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "simulator crashed")
+    }
+}
+
+impl std::error::Error for SimulatorCrashed {}
+
+struct CrashingGame {
+    nets: usize,
+    generations_left: usize,
+}
+
+impl TryGame<f64> for CrashingGame {
+    type Error = SimulatorCrashed;
+
+    fn try_run_generation(&mut self) -> Result<Vec<f64>, SimulatorCrashed> {
+        if self.generations_left == 0 {
+            return Err(SimulatorCrashed);
+        }
+        self.generations_left -= 1;
+        Ok(vec![0.; self.nets])
+    }
+
+    fn try_reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) -> Result<(), SimulatorCrashed> {
+        self.nets = nets.len();
+        Ok(())
+    }
+
+    fn try_post_training(
+        &mut self,
+        _history: Vec<HistoricTopologyLazy<f64>>,
+    ) -> Result<(), SimulatorCrashed> {
+        Ok(())
+    }
+}
+
+#[test]
+pub fn test_train_game_error() {
+    let mut game = CrashingGame {
+        nets: 0,
+        generations_left: 3,
+    };
+    let mut runner = Train::new(&mut game);
+    runner.max_individuals(20).inputs(2).outputs(2);
+    match runner.start() {
+        Err(TrainingError::Game(err)) => assert!(err.is::<SimulatorCrashed>()),
+        _ => panic!("Expected a game error"),
+    }
+    assert_eq!(runner.generation(), 3);
+
+    // The I/O errors of the code around a training can still be returned with `?`
+    let io_error = || -> Result<(), TrainingError> {
+        fs::read("missing/file.json")?;
+        Ok(())
+    };
+    assert!(matches!(io_error(), Err(TrainingError::Io(_))));
+}
+
+#[test]
+pub fn test_train_invalid_configuration() {
+    let mut game = TestGame::new();
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .max_individuals(0)
+        .max_layers(1)
+        .max_per_layers(300)
+        .delta_threshold(0.);
+    match runner.start() {
+        Err(TrainingError::InvalidConfiguration(errors)) => {
+            let parameters: Vec<&str> = errors.iter().map(|err| err.parameter).collect();
+            assert_eq!(
+                parameters,
+                vec![
+                    "outputs",
+                    "max_individuals",
+                    "max_layers",
+                    "max_per_layers",
+                    "delta_threshold"
+                ]
+            );
+        }
+        _ => panic!("Expected a configuration error"),
+    }
+}
+
+#[test]
+pub fn test_train_from_config() {
+    let config: TrainConfig<f64> = TrainConfig::from_toml(
+        r#"
+        inputs = 5
+        outputs = 5
+        iterations = 5
+        max_individuals = 30
+        save_history_to_disk = true
+
+        [mutation_probabilities]
+        change_weights = 0.9
+        guaranteed_new_neuron = 0.1
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.max_layers, TrainConfig::<f64>::default().max_layers);
+    let round_trip = TrainConfig::<f64>::from_toml(&config.to_toml().unwrap()).unwrap();
+    assert_eq!(round_trip.to_json().unwrap(), config.to_json().unwrap());
+
+    let mut game = TestGame::new();
+    let mut runner = Train::from_config(&config, &mut game);
+    runner.access_train_object(Box::new(|train| {
+        let config_file = train.history_dir().unwrap().join("config.json");
+        let saved = TrainConfig::<f64>::load(config_file).unwrap();
+        assert_eq!(saved.iterations, 5);
+    }));
+    let outcome = runner.start().unwrap();
+    assert_eq!(outcome.generations, 5);
+
+    // The config files and the builder accept the same probabilities
+    for (value, valid) in [(1.0, true), (1.5, false)] {
+        let config: TrainConfig<f64> = TrainConfig::from_toml(&format!(
+            "inputs = 5\noutputs = 5\n[mutation_probabilities]\nchange_weights = {:?}\nguaranteed_new_neuron = 0.1",
+            value
+        ))
+        .unwrap();
+        assert_eq!(config.validate().is_ok(), valid);
+        assert_eq!(MutationProbabilities::new(value, value).is_ok(), valid);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+pub fn test_train_external_evaluator() {
+    // Gives the fitness i to the network i
+    let script = r#"
+        while IFS= read -r line; do
+            case "$line" in *'"type":"end"'*) exit 0;; esac
+            count=$(printf '%s' "$line" | grep -o '"biases"' | wc -l)
+            printf '{"fitness":['
+            i=0
+            while [ "$i" -lt "$count" ]; do
+                [ "$i" -gt 0 ] && printf ','
+                printf '%s' "$i"
+                i=$((i + 1))
+            done
+            printf ']}\n'
+        done
+    "#;
+    let output = TempDir::new("external_evaluator").unwrap();
+    let mut evaluator: ExternalEvaluator<f64> =
+        ExternalEvaluator::spawn(std::process::Command::new("sh").args(["-c", script]))
+            .unwrap()
+            .output_dir(output.path())
+            .unwrap();
+    let mut runner = Train::new(&mut evaluator);
+    runner
+        .inputs(2)
+        .outputs(1)
+        .iterations(3)
+        .max_individuals(10)
+        .noise_handling(NoiseHandling::new(1));
+    assert_eq!(runner.start().unwrap().generations, 3);
+    assert!(runner.simulation.best_fitness().unwrap() >= 9.);
+    assert!(output.path().join("best.json").exists());
+    // The re-evaluations are sent with the generation of the training
+    let best_so_far: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output.path().join("best-so-far.json")).unwrap())
+            .unwrap();
+    assert_eq!(best_so_far["generation"], 2);
+    let history = fs::read_to_string(output.path().join("history.jsonl")).unwrap();
+    assert!(history.lines().count() >= 3);
+}
+
+/// Game of the workers spawned by `worker_pool`, the first worker crashes or hangs during its
+/// first batch
+struct FailingWorkerGame {
+    game: TestGame,
+    failure: Option<(String, String)>,
+}
+
+impl Game<f64> for FailingWorkerGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        if let Some((failure, marker)) = self.failure.take() {
+            if fs::File::options()
+                .write(true)
+                .create_new(true)
+                .open(marker)
+                .is_ok()
+            {
+                match failure.as_str() {
+                    "hang" => std::thread::sleep(std::time::Duration::from_secs(600)),
+                    _ => std::process::exit(1),
+                }
+            }
+        }
+        self.game.run_generation()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.game.reset_players(nets);
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+/// Entry point of the workers spawned by `worker_pool`, only run by the worker processes
+#[test]
+#[ignore = "worker process of the worker pool tests"]
+pub fn worker_process() {
+    if std::env::var(WORKER_ADDRESS_VAR).is_err() {
+        return;
+    }
+    let failure = std::env::var("NEAT_GRU_TEST_FAILURE").ok();
+    let marker = std::env::var("NEAT_GRU_TEST_FAILURE_MARKER").ok();
+    let ignore_end = failure.as_deref() == Some("ignore_end");
+    run_worker(&mut FailingWorkerGame {
+        game: TestGame::new(),
+        failure: failure.filter(|_| !ignore_end).zip(marker),
+    })
+    .unwrap();
+    if ignore_end {
+        std::thread::sleep(std::time::Duration::from_secs(600));
+    }
+}
+
+/// Runs a training with two workers, the first one fails with `failure`, "crash" or "hang",
+/// with "ignore_end" every worker keeps running after the training
+///
+/// Returns the number of restarted workers
+fn worker_pool(failure: &str, timeout: std::time::Duration) -> usize {
+    let marker_dir = tempdir::TempDir::new("worker_pool").unwrap();
+    let mut command = std::process::Command::new(std::env::current_exe().unwrap());
+    command
+        .args(["--exact", "tests::worker_process", "--ignored", "--quiet"])
+        .env("NEAT_GRU_TEST_FAILURE", failure)
+        .env(
+            "NEAT_GRU_TEST_FAILURE_MARKER",
+            marker_dir.path().join("failed"),
+        )
+        .stdout(std::process::Stdio::null());
+    let mut pool: WorkerPool<f64> = WorkerPool::new(command, 2).timeout(timeout).batch_size(7);
+    let mut runner = Train::new(&mut pool);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(3)
+        .max_individuals(30);
+    assert_eq!(runner.start().unwrap().generations, 3);
+    runner.simulation.restarts()
+}
+
+#[test]
+pub fn test_train_worker_pool() {
+    assert_eq!(worker_pool("crash", std::time::Duration::from_secs(30)), 1);
+}
+
+#[test]
+pub fn test_train_worker_pool_timeout() {
+    // The hanging worker is killed after the timeout and its batch given to another worker
+    assert_eq!(worker_pool("hang", std::time::Duration::from_secs(3)), 1);
+}
+
+#[test]
+pub fn test_train_worker_pool_shutdown_timeout() {
+    // The workers ignoring the end of the training are killed after the timeout
+    let start = std::time::Instant::now();
+    assert_eq!(
+        worker_pool("ignore_end", std::time::Duration::from_secs(3)),
+        0
+    );
+    assert!(start.elapsed() < std::time::Duration::from_secs(60));
+}
+
+#[test]
+pub fn test_train_evaluator() {
+    let inputs = [0.1, 0.2, 0.3];
+    let evaluator = |net: &mut NeuralNetwork<f64>| {
+        let output = net.compute(&inputs);
+        -inputs
+            .iter()
+            .zip(output.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>()
+    };
+    let mut game = EvaluatorGame::new(evaluator)
+        .episodes(3)
+        .aggregation(Aggregation::Median);
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(3)
+        .outputs(3)
+        .iterations(5)
+        .max_individuals(40);
+    assert_eq!(runner.start().unwrap().generations, 5);
+
+    assert_eq!(Aggregation::Mean.aggregate(vec![1.0, 2.0, 6.0]), 3.0);
+    assert_eq!(Aggregation::Min.aggregate(vec![1.0, -2.0, 6.0]), -2.0);
+    assert_eq!(Aggregation::Median.aggregate(vec![4.0, 1.0, 9.0, 2.0]), 3.0);
+}
+
+/// The network with the highest first output wins
+fn highest_output(a: &mut NeuralNetwork<f64>, b: &mut NeuralNetwork<f64>) -> (f64, f64) {
+    let (a, b) = (a.compute(&[1.0, 0.5])[0], b.compute(&[1.0, 0.5])[0]);
+    match a.partial_cmp(&b) {
+        Some(std::cmp::Ordering::Greater) => (1.0, 0.0),
+        Some(std::cmp::Ordering::Less) => (0.0, 1.0),
+        _ => (0.5, 0.5),
+    }
+}
+
+#[test]
+pub fn test_train_competitive() {
+    for schedule in [
+        PairingSchedule::RoundRobin,
+        PairingSchedule::RandomOpponents(3),
+        PairingSchedule::Swiss { rounds: 3 },
+        PairingSchedule::HallOfFame(3),
+    ] {
+        let mut evaluation = CompetitiveEvaluation::new(highest_output).schedule(schedule);
+        let mut runner = Train::new(&mut evaluation);
+        runner
+            .inputs(2)
+            .outputs(1)
+            .iterations(4)
+            .max_individuals(20)
+            .hall_of_fame(HallOfFame::new(2));
+        assert_eq!(runner.start().unwrap().generations, 4);
+        // The hall of fame of the training is given before every generation
+        assert_eq!(runner.simulation.hall_of_fame().len(), 2);
+    }
+
+    let mut coevolution = Coevolution::new(highest_output).schedule(PairingSchedule::HallOfFame(2));
+    let (mut first_population, mut second_population) = (Population::new(), Population::new());
+    let mut first = Train::new(&mut first_population);
+    first
+        .inputs(2)
+        .outputs(1)
+        .iterations(3)
+        .max_individuals(15)
+        .hall_of_fame(HallOfFame::new(3));
+    let mut second = Train::new(&mut second_population);
+    second
+        .inputs(2)
+        .outputs(1)
+        .iterations(5)
+        .max_individuals(10);
+    // The HallOfFame schedule needs the hall of fame of both trainings
+    assert!(matches!(
+        coevolution.run(&mut first, &mut second),
+        Err(TrainingError::InvalidConfiguration(_))
+    ));
+    second.hall_of_fame(HallOfFame::new(3));
+    let (first_outcome, second_outcome) = coevolution.run(&mut first, &mut second).unwrap();
+    assert_eq!(first_outcome.generations, 3);
+    assert!(second_outcome.is_cancelled());
+    assert!(!first.simulation.history().is_empty());
+    assert_eq!(first.simulation.hall_of_fame().len(), 3);
+}
+
+#[test]
+pub fn test_competitive_swiss_bye() {
+    let ev_number = EvNumber::new();
+    let nets: Vec<NeuralNetwork<f64>> = (0..3)
+        .map(|_| {
+            let topology = Topology::new_random(&mut thread_rng(), 2, 1, 4, 4, &ev_number);
+            unsafe { NeuralNetwork::new(&topology) }
+        })
+        .collect();
+    let mut evaluation =
+        CompetitiveEvaluation::new(highest_output).schedule(PairingSchedule::Swiss { rounds: 1 });
+    evaluation.reset_players(nets);
+    let fitness = evaluation.run_generation();
+    // The match scores 1 in total, the network left out is scored at the median of 0.5
+    assert_eq!(fitness.iter().sum::<f64>(), 1.5);
+    assert!(fitness.contains(&0.5));
+}
+
+#[test]
+pub fn test_coevolution_invalid_config() {
+    let mut coevolution = Coevolution::new(highest_output);
+    let (mut first_population, mut second_population) = (Population::new(), Population::new());
+    let mut first = Train::new(&mut first_population);
+    first
+        .inputs(2)
+        .outputs(1)
+        .noise_handling(NoiseHandling::new(1));
+    let mut second = Train::new(&mut second_population);
+    second
+        .inputs(2)
+        .outputs(1)
+        .local_search(LocalSearch::default());
+    match coevolution.run(&mut first, &mut second) {
+        Err(TrainingError::InvalidConfiguration(errors)) => {
+            let parameters: Vec<&str> = errors.iter().map(|error| error.parameter).collect();
+            assert_eq!(parameters, vec!["noise_handling", "local_search"]);
+        }
+        _ => panic!("noise handling and local search must be rejected"),
+    }
+
+    // A population trained on its own isn't scored NaN
+    let mut population: Population<f64> = Population::new();
+    let mut runner = Train::new(&mut population);
+    runner
+        .inputs(2)
+        .outputs(1)
+        .iterations(2)
+        .max_individuals(10);
+    assert_eq!(runner.start().unwrap().generations, 2);
+}
+
+/// Records the identity of the networks of every generation
+struct InfoGame {
+    game: TestGame,
+    generations: Vec<Vec<NetworkInfo>>,
+}
+
+impl Game<f64> for InfoGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.game.run_generation()
+    }
+
+    fn reset_players(&mut self, _nets: Vec<NeuralNetwork<f64>>) {
+        unreachable!("reset_players_with_info is overridden")
+    }
+
+    fn reset_players_with_info(&mut self, players: Vec<(NetworkInfo, NeuralNetwork<f64>)>) {
+        let (infos, nets) = players.into_iter().unzip();
+        self.generations.push(infos);
+        self.game.reset_players(nets);
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+#[test]
+pub fn test_train_network_info() {
+    let mut game = InfoGame {
+        game: TestGame::new(),
+        generations: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(6)
+        .max_individuals(50);
+    runner.start().unwrap();
+    let generations = &runner.simulation.generations;
+    assert_eq!(generations.len(), 7);
+    assert!(generations[0]
+        .iter()
+        .all(|info| info.parents.is_empty() && info.birth_generation == 0));
+    for (generation, infos) in generations.iter().enumerate().skip(1) {
+        let previous: std::collections::HashSet<u64> = generations[generation - 1]
+            .iter()
+            .map(|info| info.id)
+            .collect();
+        for info in infos {
+            assert!(info.genome_size > 0);
+            assert_eq!(info.generation, generation);
+            if info.birth_generation == generation {
+                assert!(!info.parents.is_empty());
+                assert!(info.parents.iter().all(|parent| previous.contains(parent)));
+                assert!(!previous.contains(&info.id));
+            } else {
+                // Unchanged copy of a network of a previous generation
+                assert!(info.birth_generation < generation);
+                assert!(previous.contains(&info.id));
+            }
+        }
+    }
+}
+
+/// Keeps the id of the best historical topology
+struct LineageGame {
+    game: TestGame,
+    best: Option<u64>,
+}
+
+impl Game<f64> for LineageGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.game.run_generation()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.game.reset_players(nets);
+    }
+
+    fn post_training(&mut self, history: Vec<HistoricTopologyLazy<f64>>) {
+        self.best = history
+            .into_iter()
+            .map(|historic| historic.into_historic().unwrap())
+            .max_by(|a, b| {
+                a.get_last_result()
+                    .partial_cmp(&b.get_last_result())
+                    .unwrap()
+            })
+            .map(|best| best.id());
+    }
+}
+
+#[test]
+pub fn test_train_lineage() {
+    let mut game = LineageGame {
+        game: TestGame::new(),
+        best: None,
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(8)
+        .max_individuals(40)
+        .save_history_to_disk(true)
+        .track_lineage(true);
+    runner.start().unwrap();
+    let best = runner.simulation.best.unwrap();
+    let lineage = runner.lineage();
+    let ancestry = lineage.ancestry(best);
+    assert_eq!(ancestry[0].id, best);
+    assert!(ancestry[0].fitness.is_some());
+    // The ancestry goes back to the first generation
+    assert!(ancestry
+        .iter()
+        .any(|record| record.birth_generation == 0 && record.parents.is_empty()));
+    for record in &ancestry {
+        assert!(record
+            .parents
+            .iter()
+            .all(|parent| ancestry.iter().any(|ancestor| ancestor.id == *parent)));
+        if record.birth_generation > 0 {
+            assert!(!record.operations.is_empty());
+        }
+    }
+
+    let jsonl = lineage.to_jsonl(Some(best)).unwrap();
+    assert_eq!(jsonl.lines().count(), ancestry.len());
+    let dot = lineage.to_dot(Some(best));
+    assert!(dot.starts_with("digraph lineage {"));
+    assert!(dot.contains(&format!("g{} [", best)));
+    assert_eq!(
+        lineage.to_jsonl(None).unwrap().lines().count(),
+        lineage.records().len()
+    );
+}
+
+/// Keeps the hall of fame given at the end of the training
+struct HallOfFameGame {
+    game: TestGame,
+    hall_of_fame: Vec<HallOfFameMember<f64>>,
+}
+
+impl Game<f64> for HallOfFameGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.game.run_generation()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.game.reset_players(nets);
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+
+    fn post_training_hall_of_fame(&mut self, hall_of_fame: Vec<HallOfFameMember<f64>>) {
+        self.hall_of_fame = hall_of_fame;
+    }
+}
+
+#[test]
+pub fn test_train_hall_of_fame() {
+    let mut game = HallOfFameGame {
+        game: TestGame::new(),
+        hall_of_fame: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(10)
+        .max_individuals(30)
+        .hall_of_fame(HallOfFame::new(5).reinjected(2));
+    let mut best_scores = Vec::new();
+    runner.access_train_object(Box::new(|train| {
+        assert!(train.hall_of_fame_members().len() <= 5);
+    }));
+    for _ in 0..10 {
+        best_scores.push(runner.step().unwrap().best_score);
+    }
+    runner.finish().unwrap();
+    let hall_of_fame = &runner.simulation.hall_of_fame;
+    assert_eq!(hall_of_fame.len(), 5);
+    let best_ever = best_scores
+        .iter()
+        .cloned()
+        .fold(f64::NEG_INFINITY, f64::max);
+    assert_eq!(hall_of_fame[0].fitness, best_ever);
+    for (index, member) in hall_of_fame.iter().enumerate() {
+        if index > 0 {
+            assert!(member.fitness <= hall_of_fame[index - 1].fitness);
+        }
+        assert!(hall_of_fame[..index]
+            .iter()
+            .all(|other| other.topology.id() != member.topology.id()
+                && !other.topology.same_genome(&member.topology)));
+        let mut network = member.network();
+        assert_eq!(network.compute(&[0.1, 0.2, 0.3, 0.4, 0.5]).len(), 5);
+    }
+
+    let config = TrainConfig::<f64> {
+        inputs: Some(5),
+        outputs: Some(5),
+        hall_of_fame: Some(HallOfFame::new(0).reinjected(1)),
+        ..Default::default()
+    };
+    match config.validate() {
+        Err(TrainingError::InvalidConfiguration(errors)) => assert_eq!(errors.len(), 2),
+        other => panic!("Expected invalid configuration, got {:?}", other),
+    }
+}
+
+/// Keeps the generation, species and fitness of the history given at the end of the training
+struct HistoryGame {
+    game: TestGame,
+    history: Vec<(usize, usize, f64)>,
+}
+
+impl Game<f64> for HistoryGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.game.run_generation()
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.game.reset_players(nets);
+    }
+
+    fn post_training(&mut self, history: Vec<HistoricTopologyLazy<f64>>) {
+        self.history = history
+            .into_iter()
+            .map(|historic| {
+                let historic = historic.into_historic().unwrap();
+                let fitness = historic.get_last_result();
+                (historic.generation, historic.species, fitness)
+            })
+            .collect();
+    }
+}
+
+fn train_with_history_sink<H: HistorySink<f64>>(sink: H) -> Vec<(usize, usize, f64)> {
+    let mut game = HistoryGame {
+        game: TestGame::new(),
+        history: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(8)
+        .max_individuals(30)
+        .history_sink(sink);
+    runner.start().unwrap();
+    std::mem::take(&mut runner.simulation.history)
+}
+
+fn assert_history_sorted(history: &[(usize, usize, f64)]) {
+    assert!(history
+        .windows(2)
+        .all(|pair| (pair[0].0, pair[0].1) < (pair[1].0, pair[1].1)));
+}
+
+#[test]
+pub fn test_train_history_sink() {
+    let dir = TempDir::new("history_sink").unwrap();
+    let path = dir.path().join("history");
+    let history = train_with_history_sink(KeepLast::new(DirectoryHistory::new(&path).unwrap(), 3));
+    assert_history_sorted(&history);
+    let mut generations: Vec<usize> = history
+        .iter()
+        .map(|(generation, _, _)| *generation)
+        .collect();
+    generations.dedup();
+    assert_eq!(generations, vec![5, 6, 7]);
+    // The directory is kept after the training, with the config
+    assert!(path.join("config.json").exists());
+    assert_eq!(fs::read_dir(&path).unwrap().count(), history.len() + 1);
+
+    let history = train_with_history_sink(KeepEveryNth::new(MemoryHistory::new(), 3));
+    assert_history_sorted(&history);
+    let mut generations: Vec<usize> = history
+        .iter()
+        .map(|(generation, _, _)| *generation)
+        .collect();
+    generations.dedup();
+    assert_eq!(generations, vec![0, 3, 6]);
+
+    let history = train_with_history_sink(KeepImprovements::new(MemoryHistory::new()));
+    assert_history_sorted(&history);
+    let mut best_per_generation: Vec<(usize, f64)> = Vec::new();
+    for (generation, _, fitness) in history {
+        match best_per_generation.last_mut() {
+            Some((last, best)) if *last == generation => *best = best.max(fitness),
+            _ => best_per_generation.push((generation, fitness)),
+        }
+    }
+    assert_eq!(best_per_generation[0].0, 0);
+    assert!(best_per_generation
+        .windows(2)
+        .all(|pair| pair[1].1 > pair[0].1));
+}
+
+/// Gives 1 to the first evaluation of every genome and 0 to the following ones, like a lucky
+/// first episode
+struct NoisyGame {
+    evaluated: HashSet<u64>,
+    ids: Vec<u64>,
+    run_sizes: Vec<usize>,
+    evaluations: Vec<usize>,
+}
+
+impl Game<f64> for NoisyGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        self.run_sizes.push(self.ids.len());
+        let evaluated = &mut self.evaluated;
+        self.ids
+            .iter()
+            .map(|id| if evaluated.insert(*id) { 1.0 } else { 0.0 })
+            .collect()
+    }
+
+    fn reset_players(&mut self, _nets: Vec<NeuralNetwork<f64>>) {}
+
+    fn reset_players_with_info(&mut self, players: Vec<(NetworkInfo, NeuralNetwork<f64>)>) {
+        self.ids = players.iter().map(|(info, _)| info.id).collect();
+        self.evaluations.push(players[0].0.evaluation);
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+#[test]
+pub fn test_train_noise_handling() {
+    let mut game = NoisyGame {
+        evaluated: HashSet::new(),
+        ids: Vec::new(),
+        run_sizes: Vec::new(),
+        evaluations: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(5)
+        .outputs(5)
+        .iterations(6)
+        .max_individuals(40)
+        .noise_handling(NoiseHandling::new(2).candidates(3));
+    runner.init().unwrap();
+    for _ in 0..6 {
+        let report = runner.step().unwrap();
+        // The lucky first evaluations are never confirmed
+        assert!(report.best_historical_score <= 1.0 / 3.0 + 1e-9);
+    }
+    let history = runner.take_history().unwrap();
+    for historic in history {
+        let historic = historic.into_historic().unwrap();
+        let evaluations = historic.evaluations();
+        assert!(evaluations > 0);
+        assert!((historic.get_last_result() - 1.0 / evaluations as f64).abs() < 1e-9);
+    }
+    let run_sizes = &runner.simulation.run_sizes;
+    assert_eq!(run_sizes.len(), 6 * 3);
+    for generation in run_sizes.chunks(3) {
+        assert!(generation[1] >= generation[0].min(3) && generation[1] <= generation[0]);
+        assert_eq!(generation[1], generation[2]);
+    }
+    // The reevaluations of a generation are told apart from its first evaluation
+    let evaluations = &runner.simulation.evaluations;
+    assert_eq!(evaluations.len(), 6 * 3 + 1);
+    assert!(evaluations
+        .chunks(3)
+        .all(|chunk| chunk == [0, 1, 2] || chunk == [0]));
+}
+
+/// Rewards the networks whose first output is close to 0.5, keeps the results of every run
+struct TargetGame {
+    nets: Vec<NeuralNetwork<f64>>,
+    runs: Vec<Vec<f64>>,
+}
+
+impl Game<f64> for TargetGame {
+    fn run_generation(&mut self) -> Vec<f64> {
+        let results: Vec<f64> = self
+            .nets
+            .iter_mut()
+            .map(|net| -(net.compute(&[0.2, 0.4, 0.6])[0] - 0.5).abs())
+            .collect();
+        self.runs.push(results.clone());
+        results
+    }
+
+    fn reset_players(&mut self, nets: Vec<NeuralNetwork<f64>>) {
+        self.nets = nets;
+    }
+
+    fn post_training(&mut self, _history: Vec<HistoricTopologyLazy<f64>>) {}
+}
+
+#[test]
+pub fn test_train_local_search() {
+    let mut game = TargetGame {
+        nets: Vec::new(),
+        runs: Vec::new(),
+    };
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(3)
+        .outputs(1)
+        .iterations(5)
+        .max_individuals(30)
+        .track_lineage(true)
+        .local_search(
+            LocalSearch::new(LocalSearchMethod::OnePlusOne)
+                .steps(10)
+                .budget(12),
+        );
+    runner.init().unwrap();
+    let best = |results: &[f64]| results.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let mut improved = false;
+    for _ in 0..5 {
+        runner.simulation.runs.clear();
+        let report = runner.step().unwrap();
+        let runs = &runner.simulation.runs;
+        let generation_best = best(&runs[0]);
+        let evaluations: usize = runs[1..].iter().map(Vec::len).sum();
+        assert!(evaluations > 0 && evaluations <= 12);
+        // The improved weights are written back into the genomes
+        assert!(report.best_score >= generation_best);
+        improved |= report.best_score > generation_best;
+    }
+    assert!(improved);
+    // The improved genomes are new children of the tuned ones, which keep their fitness
+    let lineage = runner.lineage();
+    let tuned: Vec<_> = lineage
+        .records()
+        .iter()
+        .filter(|record| record.operations == [MutationOperation::LocalSearch])
+        .collect();
+    assert!(!tuned.is_empty());
+    for record in tuned {
+        assert_eq!(record.parents.len(), 1);
+        let parent = lineage.get(record.parents[0]).unwrap();
+        assert!(parent.fitness.is_some());
+        assert!(record.fitness.is_some());
+    }
+
+    let config = TrainConfig::<f64> {
+        inputs: Some(3),
+        outputs: Some(1),
+        local_search: Some(LocalSearch::default().step_size(0.0)),
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+/// Mean squared error of the network built from the topology, computed with `compute`
+fn sequence_loss(topology: &Topology<f64>, dataset: &[Sequence<f64>]) -> f64 {
+    let mut net = unsafe { NeuralNetwork::new(topology) };
+    let mut loss = 0.0;
+    for sequence in dataset {
+        net.reset_state();
+        let mut error = 0.0;
+        for (inputs, targets) in sequence.inputs.iter().zip(&sequence.targets) {
+            let outputs = net.compute(inputs);
+            error += outputs
+                .iter()
+                .zip(targets)
+                .map(|(output, target)| (output - target).powi(2))
+                .sum::<f64>();
+        }
+        loss += error / (sequence.inputs.len() * targets_len(sequence)) as f64;
+    }
+    loss / dataset.len() as f64
+}
+
+fn targets_len(sequence: &Sequence<f64>) -> usize {
+    sequence.targets[0].len()
+}
+
+fn random_sequences(
+    inputs: usize,
+    outputs: usize,
+    count: usize,
+    steps: usize,
+) -> Vec<Sequence<f64>> {
+    let mut rng = thread_rng();
+    let mut values = |len: usize| {
+        (0..len)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect::<Vec<f64>>()
+    };
+    (0..count)
+        .map(|_| {
+            let inputs = (0..steps).map(|_| values(inputs)).collect();
+            let targets = (0..steps).map(|_| values(outputs)).collect();
+            Sequence::new(inputs, targets)
+        })
+        .collect()
+}
+
+/// Evolves random topologies with hidden neurons and every connection type
+fn random_topology(ev_number: &EvNumber) -> Topology<f64> {
+    let proba = MutationProbabilities::new(0.3, 0.5).unwrap();
+    let mut topology = Topology::new_random(&mut thread_rng(), 3, 2, 4, 4, ev_number);
+    for _ in 0..6 {
+        topology.mutate(ev_number, &proba);
+    }
+    topology
+}
+
+#[test]
+pub fn test_fine_tuning_gradient_check() {
+    let ev_number = EvNumber::new();
+    let epsilon = 1e-6;
+    for _ in 0..10 {
+        let mut topology = random_topology(&ev_number);
+        // Moves the new genes away from the kink of relu at a zero weight
+        let mut rng = thread_rng();
+        let jittered: Vec<f64> = parameters(&topology)
+            .into_iter()
+            .map(|parameter| parameter + rng.gen_range(-0.1..0.1))
+            .collect();
+        set_parameters(&mut topology, &jittered);
+        let dataset = random_sequences(3, 2, 2, 5);
+        let (loss, gradients) = loss_and_gradients(&topology, &dataset).unwrap();
+        // The forward pass is the one of `NeuralNetwork::compute`
+        assert!((loss - sequence_loss(&topology, &dataset)).abs() < 1e-9);
+        let parameters = parameters(&topology);
+        assert_eq!(parameters.len(), gradients.len());
+        for (index, gradient) in gradients.iter().enumerate() {
+            let mut shifted = parameters.clone();
+            shifted[index] = parameters[index] + epsilon;
+            set_parameters(&mut topology, &shifted);
+            let loss_plus = sequence_loss(&topology, &dataset);
+            shifted[index] = parameters[index] - epsilon;
+            set_parameters(&mut topology, &shifted);
+            let loss_minus = sequence_loss(&topology, &dataset);
+            set_parameters(&mut topology, &parameters);
+            let numerical = (loss_plus - loss_minus) / (2.0 * epsilon);
+            assert!(
+                (numerical - gradient).abs() <= 1e-5 + 1e-3 * numerical.abs(),
+                "parameter {}: numerical {} analytical {}",
+                index,
+                numerical,
+                gradient
+            );
+        }
+    }
+}
+
+#[test]
+pub fn test_fine_tuning() {
+    let ev_number = EvNumber::new();
+    let dataset = random_sequences(3, 2, 4, 6);
+    for optimizer in [Optimizer::adam(0.01), Optimizer::sgd(0.05)] {
+        let mut topology = random_topology(&ev_number);
+        let initial = sequence_loss(&topology, &dataset);
+        let losses = FineTuner::new(optimizer)
+            .epochs(50)
+            .batch_size(4)
+            .fit(&mut topology, &dataset)
+            .unwrap();
+        assert_eq!(losses.len(), 50);
+        assert!((losses[0] - initial).abs() < 1e-9);
+        // The tuned weights are written back into the topology
+        assert!(sequence_loss(&topology, &dataset) < initial);
+    }
+
+    let mut topology = random_topology(&ev_number);
+    let invalid = random_sequences(2, 2, 1, 3);
+    assert!(matches!(
+        FineTuner::new(Optimizer::adam(0.01)).fit(&mut topology, &invalid),
+        Err(TrainingError::InvalidDataset(_))
+    ));
+}
+
+#[test]
+pub fn test_dataset_game() {
+    let dir = TempDir::new("dataset").unwrap();
+    let csv = dir.path().join("dataset.csv");
+    fs::write(
+        &csv,
+        "sequence,a,b,target\n0,0,1,1\n0,1,1,0\n\n1,1,0,1\n2,0,0,0\n2,1,0,1\n",
+    )
+    .unwrap();
+    let sequences: Vec<Sequence<f64>> = read_csv(&csv, 2).unwrap();
+    assert_eq!(sequences.len(), 3);
+    assert_eq!(sequences[0].inputs, vec![vec![0.0, 1.0], vec![1.0, 1.0]]);
+    assert_eq!(sequences[0].targets, vec![vec![1.0], vec![0.0]]);
+    assert_eq!(sequences[2].inputs.len(), 2);
+    fs::write(&csv, "sequence,a,b,target\n0,0,x,1\n").unwrap();
+    assert!(read_csv::<f64, _>(&csv, 2).is_err());
+
+    let jsonl = dir.path().join("dataset.jsonl");
+    let lines: Vec<String> = sequences
+        .iter()
+        .map(|sequence| serde_json::to_string(sequence).unwrap())
+        .collect();
+    fs::write(&jsonl, lines.join("\n")).unwrap();
+    assert_eq!(read_json_lines::<f64, _>(&jsonl).unwrap(), sequences);
+
+    assert!((Loss::MeanSquaredError.score(&[0.5f64, 0.0], &[1.0, 1.0]) + 0.625).abs() < 1e-12);
+    assert!((Loss::MeanAbsoluteError.score(&[0.5f64, 0.0], &[1.0, 1.0]) + 0.75).abs() < 1e-12);
+    assert!((Loss::CrossEntropy.score(&[0.0], &[1.0]) - 0.5f64.ln()).abs() < 1e-12);
+    assert!((Loss::CrossEntropy.score(&[0.3, 0.3], &[0.0, 1.0]) - 0.5f64.ln()).abs() < 1e-12);
+    assert_eq!(Loss::Accuracy.score(&[0.2], &[1.0]), 1.0);
+    assert_eq!(
+        Loss::Accuracy.score(&[0.2, 0.5, -0.1], &[0.0, 0.0, 1.0]),
+        0.0
+    );
+
+    let (training, validation) = split(sequences.clone(), 1.0 / 3.0);
+    assert_eq!((training.len(), validation.len()), (2, 1));
+    let mut game = DatasetGame::new(training, Loss::Accuracy)
+        .unwrap()
+        .validation(validation)
+        .unwrap();
+    assert_eq!((game.inputs(), game.outputs()), (2, 1));
+    let (inputs, outputs) = (game.inputs(), game.outputs());
+    {
+        let mut runner = Train::new(&mut game);
+        runner
+            .inputs(inputs)
+            .outputs(outputs)
+            .iterations(3)
+            .max_individuals(20)
+            .noise_handling(NoiseHandling::new(2))
+            .local_search(LocalSearch::default().budget(10));
+        runner.start().unwrap();
+    }
+    // One score per generation, without the networks evaluated again
+    let scores = game.validation_scores();
+    assert_eq!(scores.len(), 3);
+    assert!(scores.iter().all(|score| (0.0..=1.0).contains(score)));
+
+    let invalid = vec![Sequence::new(vec![vec![0.0, 1.0]], vec![vec![1.0, 0.0]])];
+    assert!(matches!(
+        game.validation(invalid),
+        Err(TrainingError::InvalidDataset(_))
+    ));
+    assert!(matches!(
+        DatasetGame::<f64>::new(Vec::new(), Loss::MeanSquaredError),
+        Err(TrainingError::InvalidDataset(_))
+    ));
+}
+
+#[test]
+pub fn test_benchmarks() {
+    let ev_number = EvNumber::new();
+    let proba = MutationProbabilities::new(0.5, 0.5).unwrap();
+    for benchmark in benchmarks::standard() {
+        let mut topology = Topology::new_random(
+            &mut thread_rng(),
+            benchmark.inputs(),
+            benchmark.outputs(),
+            4,
+            4,
+            &ev_number,
+        );
+        for _ in 0..5 {
+            topology.mutate(&ev_number, &proba);
+        }
+        let mut net = unsafe { NeuralNetwork::new(&topology) };
+        let fitness = benchmark.evaluate(&mut net);
+        // The benchmarks are deterministic
+        assert_eq!(
+            fitness,
+            benchmark.evaluate(&mut net),
+            "{}",
+            benchmark.name()
+        );
+        assert!(fitness >= 0.0, "{}", benchmark.name());
+        assert!(
+            fitness <= 2.0 * benchmark.solved_threshold(),
+            "{}",
+            benchmark.name()
+        );
+    }
+    let names: HashSet<String> = benchmarks::standard().iter().map(|b| b.name()).collect();
+    assert_eq!(names.len(), benchmarks::standard().len());
+    assert_eq!(PoleBalancing::double(false).inputs(), 3);
+    assert_eq!(PoleBalancing::double(true).inputs(), 6);
+    assert!(Parity::xor().is_solved(4.0) && !Parity::xor().is_solved(3.9));
+
+    let mut game = benchmarks::game(benchmarks::MemoryCount::new().length(20));
+    let mut runner = Train::new(&mut game);
+    runner
+        .inputs(4)
+        .outputs(4)
+        .iterations(2)
+        .max_individuals(20);
+    runner.start().unwrap();
+}
+
+/// Solved by every network
+struct AlwaysSolved;
+
+impl Evaluator<f64> for AlwaysSolved {
+    fn evaluate(&self, net: &mut NeuralNetwork<f64>) -> f64 {
+        net.compute(&[1.0])[0].abs()
+    }
+}
+
+impl Benchmark for AlwaysSolved {
+    fn name(&self) -> String {
+        "always, solved".to_string()
+    }
+
+    fn inputs(&self) -> usize {
+        1
+    }
+
+    fn outputs(&self) -> usize {
+        1
+    }
+
+    fn solved_threshold(&self) -> f64 {
+        0.0
+    }
+}
+
+#[test]
+pub fn test_benchmark_harness() {
+    let config = |iterations| TrainConfig {
+        iterations,
+        max_individuals: 20,
+        ..Default::default()
+    };
+    let results = Harness::new()
+        .configuration("short", config(2))
+        .configuration("long", config(4))
+        .benchmark(|_| Parity::xor())
+        .benchmark(|_| AlwaysSolved)
+        .seeds(0..3)
+        .run()
+        .unwrap();
+    assert_eq!(results.runs.len(), 12);
+    for run in &results.runs {
+        if run.benchmark == "xor" {
+            let iterations = if run.configuration == "short" { 2 } else { 4 };
+            assert!(run.generations <= iterations);
+            assert!(run.genome_size > 0);
+        } else {
+            // Stops at the first generation solving the benchmark
+            assert_eq!((run.generations_to_solve, run.generations), (Some(1), 1));
+        }
+    }
+    let summaries = results.summaries();
+    assert_eq!(summaries.len(), 4);
+    assert!(summaries.iter().all(|summary| summary.runs == 3));
+    assert_eq!(summaries[1].solved, 3);
+    assert_eq!(summaries[1].generations_to_solve.unwrap().mean, 1.0);
+
+    let csv = results.to_csv();
+    assert_eq!(csv.lines().count(), 13);
+    assert!(csv.contains("\"always, solved\""));
+    let markdown = results.to_markdown();
+    assert_eq!(markdown.lines().count(), 6);
+    assert!(markdown.contains("| 3/3 | 1.00 ± 0.00 |"));
+
+    let statistics = Statistics::new(vec![1.0, 2.0, 3.0]).unwrap();
+    assert_eq!(statistics.mean, 2.0);
+    assert_eq!(statistics.standard_deviation, 1.0);
+    assert!(Statistics::new(Vec::new()).is_none());
+}
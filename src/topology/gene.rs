@@ -1,5 +1,6 @@
 use crate::topology::connection_type::ConnectionType;
 use crate::train::evolution_number::EvNumber;
+use crate::utils::floats_almost_equal;
 use core::cmp::Ordering;
 use num::traits::Float;
 use numeric_literals::replace_numeric_literals;
@@ -226,6 +227,30 @@ where
     }
 }
 
+impl<T> Gene<T>
+where
+    T: Float,
+{
+    /// Returns true if both genes connect the same neurons with the same weights, unlike `==`
+    /// which only compares the outputs
+    pub fn same_weights(&self, other: &Gene<T>) -> bool {
+        self.input == other.input
+            && self.output == other.output
+            && self.disabled == other.disabled
+            && self.connection_type.to_int() == other.connection_type.to_int()
+            && [
+                (self.input_weight, other.input_weight),
+                (self.memory_weight, other.memory_weight),
+                (self.reset_input_weight, other.reset_input_weight),
+                (self.update_input_weight, other.update_input_weight),
+                (self.reset_memory_weight, other.reset_memory_weight),
+                (self.update_memory_weight, other.update_memory_weight),
+            ]
+            .iter()
+            .all(|&(a, b)| floats_almost_equal(a, b))
+    }
+}
+
 impl<T> PartialEq for Gene<T>
 where
     T: Float,
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -19,7 +20,7 @@ impl fmt::Display for ProbabilitiesError {
 /// `change_weights`: Every weights will be added a value following a normal distribution ~N(0, 0.1)  
 ///
 /// `guaranteed_new_neuron`: Creates a new neuron randomly if within bounds of max_layers and max_per_layers  
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MutationProbabilities {
     pub(crate) change_weights: f64,
     pub(crate) guaranteed_new_neuron: f64,
@@ -35,16 +36,22 @@ impl MutationProbabilities {
         change_weights: f64,
         guaranteed_new_neuron: f64,
     ) -> Result<MutationProbabilities, ProbabilitiesError> {
-        let range = 0.0..1.0;
-        if !range.contains(&guaranteed_new_neuron) || !range.contains(&change_weights) {
-            Err(ProbabilitiesError {})
+        let probabilities = MutationProbabilities {
+            change_weights,
+            guaranteed_new_neuron,
+        };
+        if probabilities.is_valid() {
+            Ok(probabilities)
         } else {
-            Ok(MutationProbabilities {
-                change_weights,
-                guaranteed_new_neuron,
-            })
+            Err(ProbabilitiesError {})
         }
     }
+
+    /// Whether both probabilities are in [0.0, 1.0], also checked by `TrainConfig::validate`
+    pub(crate) fn is_valid(&self) -> bool {
+        let range = 0.0..=1.0;
+        range.contains(&self.change_weights) && range.contains(&self.guaranteed_new_neuron)
+    }
 }
 
 unsafe impl Send for MutationProbabilities {}
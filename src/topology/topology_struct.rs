@@ -14,12 +14,36 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
 const NORMAL_STDDEV: f64 = 0.04;
 
+static NEXT_GENOME_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Returns an id that no other topology of this process has
+fn next_genome_id() -> u64 {
+    NEXT_GENOME_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 pub type GeneSmrtPtr<T> = Rc<RefCell<Gene<T>>>;
 
+/// Operation applied to the parents of a topology to create it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationOperation {
+    ChangeWeights,
+    AddNode,
+    AddConnection,
+    /// A neuron without inputs or outputs was removed
+    RemoveNeuron,
+    Crossover,
+    /// The topology was copied from another island, see `Islands`
+    Migration,
+    /// The weights were tuned by local search, see `Train::local_search`
+    LocalSearch,
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Topology<T>
 where
@@ -33,6 +57,18 @@ where
     pub output_bias: Vec<Bias<T>>,
     pub genes_point: HashMap<Point, BiasAndGenes<T>>,
     genes_ev_number: HashMap<usize, GeneSmrtPtr<T>>,
+    #[serde(skip, default = "next_genome_id")]
+    id: u64,
+    #[serde(skip)]
+    parents: Vec<u64>,
+    #[serde(skip)]
+    birth_generation: Option<usize>,
+    #[serde(skip)]
+    operations: Vec<MutationOperation>,
+    #[serde(skip)]
+    evaluations: usize,
+    #[serde(skip)]
+    objective: Option<T>,
 }
 
 pub type TopologySmrtPtr<T> = Arc<Mutex<Topology<T>>>;
@@ -94,6 +130,12 @@ where
             output_bias: self.output_bias.clone(),
             genes_point,
             genes_ev_number,
+            id: self.id,
+            parents: self.parents.clone(),
+            birth_generation: self.birth_generation,
+            operations: self.operations.clone(),
+            evaluations: self.evaluations,
+            objective: self.objective,
         }
     }
 }
@@ -112,7 +154,98 @@ where
             output_bias: Vec::new(),
             genes_point: HashMap::new(),
             genes_ev_number: HashMap::new(),
+            id: next_genome_id(),
+            parents: Vec::new(),
+            birth_generation: None,
+            operations: Vec::new(),
+            evaluations: 0,
+            objective: None,
+        }
+    }
+
+    /// Returns the id of the genome, kept by its unchanged copies and unique in the process
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the ids of the topologies this one was mutated or crossed over from
+    pub fn parents(&self) -> &[u64] {
+        &self.parents
+    }
+
+    /// Returns the generation in which the topology was first evaluated
+    pub fn birth_generation(&self) -> Option<usize> {
+        self.birth_generation
+    }
+
+    pub(crate) fn set_birth_generation(&mut self, generation: usize) {
+        self.birth_generation = Some(generation);
+    }
+
+    /// Returns the operations applied to the parents to create this topology
+    pub fn operations(&self) -> &[MutationOperation] {
+        &self.operations
+    }
+
+    /// Returns true if both topologies have the same neurons, enabled genes, weights and biases,
+    /// whatever their ids
+    pub fn same_genome(&self, other: &Topology<T>) -> bool {
+        if self.layers_sizes != other.layers_sizes
+            || self.output_bias != other.output_bias
+            || self.genes_point.len() != other.genes_point.len()
+        {
+            return false;
         }
+        let enabled_genes = |bias_and_genes: &BiasAndGenes<T>| {
+            let mut genes: Vec<Gene<T>> = bias_and_genes
+                .genes
+                .iter()
+                .map(|gene| gene.borrow().clone())
+                .filter(|gene| !gene.disabled)
+                .collect();
+            genes.sort();
+            genes
+        };
+        self.genes_point.iter().all(|(point, bias_and_genes)| {
+            other
+                .genes_point
+                .get(point)
+                .is_some_and(|other_bias_and_genes| {
+                    let (genes, other_genes) = (
+                        enabled_genes(bias_and_genes),
+                        enabled_genes(other_bias_and_genes),
+                    );
+                    bias_and_genes.bias == other_bias_and_genes.bias
+                        && genes.len() == other_genes.len()
+                        && genes
+                            .iter()
+                            .zip(other_genes.iter())
+                            .all(|(gene, other)| gene.same_weights(other))
+                })
+        })
+    }
+
+    /// Restores the id of a topology read from the disk
+    pub(crate) fn set_id(&mut self, id: u64) {
+        self.id = id;
+    }
+
+    /// Returns a copy with a new id whose only parent is this topology
+    pub(crate) fn derive(&self, operation: MutationOperation) -> Topology<T> {
+        let mut child = self.clone();
+        child.set_parents(vec![self.id]);
+        child.operations.push(operation);
+        child
+    }
+
+    /// Gives a new identity to a child of `parents`
+    fn set_parents(&mut self, parents: Vec<u64>) {
+        self.id = next_genome_id();
+        self.parents = parents;
+        self.birth_generation = None;
+        self.operations.clear();
+        self.evaluations = 0;
+        self.objective = None;
     }
 
     #[replace_numeric_literals(T::from(literal).unwrap())]
@@ -219,27 +352,68 @@ where
     fn generate_output_bias(&mut self, rng: &mut ThreadRng) {
         let last_layer_size = self.layers_sizes.last().unwrap();
         self.output_bias = (0..*last_layer_size)
-            .into_iter()
             .map(|_| Bias::new_random(rng))
             .collect();
     }
 
     fn uniform_output_bias(&mut self) {
         let last_layer_size = self.layers_sizes.last().unwrap();
-        self.output_bias = (0..*last_layer_size)
-            .into_iter()
-            .map(|_| Bias::new_zero())
-            .collect();
+        self.output_bias = (0..*last_layer_size).map(|_| Bias::new_zero()).collect();
     }
 
     pub fn set_last_result(&mut self, result: T) {
         self.last_result = result;
+        self.objective = None;
     }
 
     pub fn get_last_result(&self) -> T {
         self.last_result
     }
 
+    /// Returns the raw fitness of the last evaluation, the last result is the selection score
+    /// when it differs, e.g. with novelty search or multi-objective training
+    pub fn objective(&self) -> T {
+        self.objective.unwrap_or(self.last_result)
+    }
+
+    /// Keeps the raw fitness of a genome whose last result was replaced by a selection score
+    pub(crate) fn set_objective(&mut self, objective: T) {
+        self.objective = Some(objective);
+    }
+
+    /// Returns the number of evaluations averaged in the last result when noise handling is
+    /// enabled, see `Train::noise_handling`
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    /// Averages the mean of `count` new evaluations with the previous evaluations of the genome
+    pub(crate) fn add_evaluations(&mut self, mean: T, count: usize) {
+        let total = self.evaluations + count;
+        self.last_result = if self.evaluations == 0 {
+            mean
+        } else {
+            (self.last_result * T::from(self.evaluations).unwrap() + mean * T::from(count).unwrap())
+                / T::from(total).unwrap()
+        };
+        self.evaluations = total;
+        self.objective = None;
+    }
+
+    /// Returns the number of enabled genes
+    pub fn gene_count(&self) -> usize {
+        self.genes_point
+            .values()
+            .map(|b_and_g| {
+                b_and_g
+                    .genes
+                    .iter()
+                    .filter(|gene| !gene.borrow().disabled)
+                    .count()
+            })
+            .sum()
+    }
+
     pub fn new_generation(
         &self,
         new_topologies: &mut Vec<Arc<Mutex<Topology<T>>>>,
@@ -249,6 +423,7 @@ where
     ) {
         for _ in 0..reproduction_count {
             let mut cp = self.clone();
+            cp.set_parents(vec![self.id]);
             cp.mutate(ev_number, proba);
             new_topologies.push(Arc::new(Mutex::new(cp)));
         }
@@ -291,6 +466,31 @@ where
         }
     }
 
+    /// Adds a gaussian perturbation to every weight and bias, without changing the structure
+    pub(crate) fn perturb_weights(&mut self, stddev: f64, rng: &mut ThreadRng) {
+        let normal = Normal::new(0.0, stddev).unwrap();
+        let mut sample = || T::from(normal.sample(rng)).unwrap();
+        for gene in self.genes_ev_number.values() {
+            let mut gene = gene.borrow_mut();
+            gene.input_weight += sample();
+            gene.memory_weight += sample();
+            gene.reset_input_weight += sample();
+            gene.update_input_weight += sample();
+            gene.reset_memory_weight += sample();
+            gene.update_memory_weight += sample();
+        }
+        let biases = self
+            .genes_point
+            .values_mut()
+            .map(|gene_and_bias| &mut gene_and_bias.bias)
+            .chain(self.output_bias.iter_mut());
+        for bias in biases {
+            bias.bias_input += sample();
+            bias.bias_update += sample();
+            bias.bias_reset += sample();
+        }
+    }
+
     #[inline]
     pub fn insert_gene(&mut self, gene: GeneSmrtPtr<T>) {
         let (input, ev_number) = {
@@ -316,7 +516,7 @@ where
         self.layers_sizes.insert(layer as usize, 1);
 
         for gene_rc in self.genes_ev_number.values_mut() {
-            let mut gene = &mut *gene_rc.borrow_mut();
+            let gene = &mut *gene_rc.borrow_mut();
             if gene.input.layer >= layer {
                 gene.input.layer += 1;
             }
@@ -344,8 +544,8 @@ where
     fn add_node(&mut self, ev_number: &EvNumber, rng: &mut ThreadRng) {
         let non_disabled_connections = self
             .genes_ev_number
-            .iter()
-            .filter_map(|(_ev, gene_rc)| {
+            .values()
+            .filter_map(|gene_rc| {
                 if !gene_rc.borrow().disabled {
                     Some(gene_rc.clone())
                 } else {
@@ -357,7 +557,7 @@ where
         let gene_to_split_index = rng.gen_range(0..non_disabled_connections.len());
         let gene_to_split = &non_disabled_connections[gene_to_split_index];
         let (mut original_gene, should_create_new_layer) = {
-            let mut gene = &mut *gene_to_split.borrow_mut();
+            let gene = &mut *gene_to_split.borrow_mut();
             let should_create_new_layer = gene.output.layer - gene.input.layer >= 2;
             if should_create_new_layer && self.layers_sizes.len() >= self.max_layers {
                 return;
@@ -422,12 +622,15 @@ where
         let change_weights = rng.gen_range(0.0..1.);
         if change_weights < proba.change_weights {
             self.change_weights(&mut rng);
+            self.operations.push(MutationOperation::ChangeWeights);
         } else {
             let change_topology = rng.gen_range(0.0..1.);
             if change_topology > proba.guaranteed_new_neuron {
                 self.add_node(ev_number, &mut rng);
+                self.operations.push(MutationOperation::AddNode);
             } else {
                 self.add_connection(ev_number, &mut rng);
+                self.operations.push(MutationOperation::AddConnection);
             }
         }
         loop {
@@ -466,11 +669,9 @@ where
             if dont_have_outputs.is_empty() && dont_have_inputs.is_empty() {
                 break;
             }
-            for gene in &dont_have_outputs {
-                self.remove_neuron(gene);
-            }
-            for gene in &dont_have_inputs {
+            for gene in dont_have_outputs.iter().chain(dont_have_inputs.iter()) {
                 self.remove_neuron(gene);
+                self.operations.push(MutationOperation::RemoveNeuron);
             }
         }
     }
@@ -719,17 +920,25 @@ where
             output_bias,
             genes_point,
             genes_ev_number,
+            id: next_genome_id(),
+            parents: Vec::new(),
+            birth_generation: None,
+            operations: Vec::new(),
+            evaluations: 0,
+            objective: None,
         }
     }
 
     #[inline]
     pub fn crossover(best: &Topology<T>, worst: &Topology<T>) -> TopologySmrtPtr<T> {
         let mut new_topology = best.clone();
+        new_topology.set_parents(vec![best.id, worst.id]);
+        new_topology.operations.push(MutationOperation::Crossover);
         for (ev_number, worst_gene) in worst.genes_ev_number.iter() {
             let cell = &**worst_gene;
             let worst_gene = &mut *cell.borrow_mut();
             // If gene exists in both topologies, adjust weight to the average between the 2
-            if new_topology.genes_ev_number.get(ev_number).is_none() {
+            if !new_topology.genes_ev_number.contains_key(ev_number) {
                 // If gene only exists in the worst topology, try to add it only if the neuron
                 // exists in the best topology
                 // Only crossover this gene if both inputs and outputs neuron already exist
@@ -776,8 +985,8 @@ where
         biases.append(&mut output_biases);
         let genes = self
             .genes_point
-            .iter()
-            .flat_map(|(_point, gene)| {
+            .values()
+            .flat_map(|gene| {
                 gene.genes
                     .iter()
                     .map(|gene| {
@@ -0,0 +1,309 @@
+use crate::topology::mutation_probabilities::MutationProbabilities;
+use crate::train::error::{ConfigurationError, TrainingError};
+use crate::train::hall_of_fame::HallOfFame;
+use crate::train::local_search::LocalSearch;
+use crate::train::noise::NoiseHandling;
+use crate::train::novelty::NoveltySearch;
+use crate::train::speciation::SpeciesRepresentative;
+use crate::train::InvalidFitnessPolicy;
+use num::Float;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::path::Path;
+
+/// Hyperparameters of a training, set with the setters of `Train` or loaded from a file
+///
+/// Missing fields take the same default values as `Train::new`
+///
+/// ```toml
+/// inputs = 5
+/// outputs = 2
+/// iterations = 300
+/// delta_threshold = 2.5
+///
+/// [mutation_probabilities]
+/// change_weights = 0.9
+/// guaranteed_new_neuron = 0.1
+/// ```
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrainConfig<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Maximum number of generations to be run, defaults to 1000
+    pub iterations: usize,
+    /// Number of networks per generation, defaults to 100
+    pub max_individuals: usize,
+    /// Maximum number of layers of the networks, defaults to 4
+    pub max_layers: usize,
+    /// Maximum number of neurons per layer, defaults to 20
+    pub max_per_layers: usize,
+    /// Distance above which two topologies don't belong to the same species, defaults to 3
+    pub delta_threshold: F,
+    /// Weight of the disjoint genes in the compatibility formula, defaults to 1
+    pub c1: F,
+    /// Weight of the excess genes in the compatibility formula, defaults to 1
+    pub c2: F,
+    /// Weight of the mean weight difference in the compatibility formula, defaults to 1
+    pub c3: F,
+    /// Whether crossovers are run, defaults to true
+    pub crossovers: bool,
+    pub species_representative: SpeciesRepresentative,
+    /// Number of neurons on the first layer, mandatory
+    pub inputs: Option<usize>,
+    /// Number of neurons on the last layer, mandatory
+    pub outputs: Option<usize>,
+    /// Whether the history is saved on the disk instead of the RAM, defaults to false
+    pub save_history_to_disk: bool,
+    /// Whether the complexity is an objective of multi-objective trainings, defaults to false
+    pub complexity_objective: bool,
+    pub mutation_probabilities: MutationProbabilities,
+    pub novelty_search: Option<NoveltySearch<F>>,
+    pub invalid_fitness_policy: InvalidFitnessPolicy,
+    /// Whether every genome is recorded in the lineage log, defaults to false
+    pub track_lineage: bool,
+    pub hall_of_fame: Option<HallOfFame>,
+    pub noise_handling: Option<NoiseHandling>,
+    pub local_search: Option<LocalSearch>,
+}
+
+impl<F> Default for TrainConfig<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    fn default() -> Self {
+        TrainConfig {
+            iterations: 1000,
+            max_individuals: 100,
+            max_layers: 4,
+            max_per_layers: 20,
+            delta_threshold: F::from(3).unwrap(),
+            c1: F::one(),
+            c2: F::one(),
+            c3: F::one(),
+            crossovers: true,
+            species_representative: SpeciesRepresentative::Best,
+            inputs: None,
+            outputs: None,
+            save_history_to_disk: false,
+            complexity_objective: false,
+            mutation_probabilities: MutationProbabilities {
+                change_weights: 0.95,
+                guaranteed_new_neuron: 0.2,
+            },
+            novelty_search: None,
+            invalid_fitness_policy: InvalidFitnessPolicy::TreatAsMinimum,
+            track_lineage: false,
+            hall_of_fame: None,
+            noise_handling: None,
+            local_search: None,
+        }
+    }
+}
+
+impl<F> TrainConfig<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Checks every hyperparameter, returns all the invalid ones at once
+    pub fn validate(&self) -> Result<(), TrainingError> {
+        let mut errors = Vec::new();
+        let mut check = |valid: bool, parameter: &'static str, reason: &str| {
+            if !valid {
+                errors.push(ConfigurationError {
+                    parameter,
+                    reason: reason.to_string(),
+                });
+            }
+        };
+        let max_neurons = u8::MAX as usize;
+        match self.inputs {
+            Some(inputs) => check(
+                inputs > 0 && inputs <= max_neurons,
+                "inputs",
+                "must be between 1 and 255",
+            ),
+            None => check(false, "inputs", "must be set"),
+        }
+        match self.outputs {
+            Some(outputs) => check(
+                outputs > 0 && outputs <= max_neurons,
+                "outputs",
+                "must be between 1 and 255",
+            ),
+            None => check(false, "outputs", "must be set"),
+        }
+        check(
+            self.max_individuals > 0,
+            "max_individuals",
+            "must be at least 1",
+        );
+        check(
+            self.max_layers >= 2 && self.max_layers <= max_neurons,
+            "max_layers",
+            "must be between 2 and 255",
+        );
+        check(
+            self.max_per_layers > 0 && self.max_per_layers <= max_neurons,
+            "max_per_layers",
+            "must be between 1 and 255",
+        );
+        check(
+            self.delta_threshold.is_finite() && self.delta_threshold > F::zero(),
+            "delta_threshold",
+            "must be finite and positive",
+        );
+        for (parameter, value) in [("c1", self.c1), ("c2", self.c2), ("c3", self.c3)] {
+            check(
+                value.is_finite() && value >= F::zero(),
+                parameter,
+                "must be finite and not negative",
+            );
+        }
+        check(
+            self.mutation_probabilities.is_valid(),
+            "mutation_probabilities",
+            "must be between 0 and 1",
+        );
+        if let Some(novelty) = &self.novelty_search {
+            check(novelty.k > 0, "novelty_search", "k must be at least 1");
+            check(
+                novelty.novelty_weight >= F::zero() && novelty.novelty_weight <= F::one(),
+                "novelty_search",
+                "novelty_weight must be between 0 and 1",
+            );
+        }
+        if let Some(hall_of_fame) = &self.hall_of_fame {
+            check(
+                hall_of_fame.size > 0,
+                "hall_of_fame",
+                "size must be at least 1",
+            );
+            check(
+                hall_of_fame.reinjected <= hall_of_fame.size,
+                "hall_of_fame",
+                "reinjected must not be above size",
+            );
+        }
+        if let Some(local_search) = &self.local_search {
+            check(
+                local_search.step_size.is_finite() && local_search.step_size > 0.0,
+                "local_search",
+                "step_size must be positive",
+            );
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TrainingError::InvalidConfiguration(errors))
+        }
+    }
+
+    /// Converts the float parameters to f64, used to write the config of any training
+    pub(crate) fn to_f64(&self) -> TrainConfig<f64> {
+        let to_f64 = |value: F| value.to_f64().unwrap_or(f64::NAN);
+        TrainConfig {
+            iterations: self.iterations,
+            max_individuals: self.max_individuals,
+            max_layers: self.max_layers,
+            max_per_layers: self.max_per_layers,
+            delta_threshold: to_f64(self.delta_threshold),
+            c1: to_f64(self.c1),
+            c2: to_f64(self.c2),
+            c3: to_f64(self.c3),
+            crossovers: self.crossovers,
+            species_representative: self.species_representative,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            save_history_to_disk: self.save_history_to_disk,
+            complexity_objective: self.complexity_objective,
+            mutation_probabilities: self.mutation_probabilities.clone(),
+            novelty_search: self.novelty_search.as_ref().map(|novelty| NoveltySearch {
+                k: novelty.k,
+                novelty_weight: to_f64(novelty.novelty_weight),
+                archive_threshold: to_f64(novelty.archive_threshold),
+                max_archive_size: novelty.max_archive_size,
+            }),
+            invalid_fitness_policy: self.invalid_fitness_policy,
+            track_lineage: self.track_lineage,
+            hall_of_fame: self.hall_of_fame.clone(),
+            noise_handling: self.noise_handling.clone(),
+            local_search: self.local_search.clone(),
+        }
+    }
+}
+
+impl<F> TrainConfig<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send + Serialize + DeserializeOwned,
+{
+    /// Parses a config written in TOML
+    pub fn from_toml(toml: &str) -> Result<TrainConfig<F>, TrainingError> {
+        toml::from_str(toml).map_err(|err| TrainingError::Serialization {
+            context: "the TOML config".to_string(),
+            source: Box::new(err),
+        })
+    }
+
+    /// Parses a config written in JSON
+    pub fn from_json(json: &str) -> Result<TrainConfig<F>, TrainingError> {
+        serde_json::from_str(json).map_err(|err| TrainingError::Serialization {
+            context: "the JSON config".to_string(),
+            source: Box::new(err),
+        })
+    }
+
+    /// Loads a config file, written in TOML if its extension is `.toml`, in JSON otherwise
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the config file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<TrainConfig<F>, TrainingError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(TrainingError::checkpoint(path))?;
+        if Self::is_toml(path) {
+            Self::from_toml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    /// Writes the config to a file, in TOML if its extension is `.toml`, in JSON otherwise
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the config file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TrainingError> {
+        let path = path.as_ref();
+        let content = if Self::is_toml(path) {
+            self.to_toml()?
+        } else {
+            self.to_json()?
+        };
+        std::fs::write(path, content).map_err(TrainingError::checkpoint(path))
+    }
+
+    pub fn to_toml(&self) -> Result<String, TrainingError> {
+        // Going through a `Value` writes the plain values before the tables as TOML requires
+        toml::Value::try_from(self)
+            .and_then(|value| toml::to_string(&value))
+            .map_err(|err| TrainingError::Serialization {
+                context: "the TOML config".to_string(),
+                source: Box::new(err),
+            })
+    }
+
+    pub fn to_json(&self) -> Result<String, TrainingError> {
+        serde_json::to_string_pretty(self).map_err(|err| TrainingError::Serialization {
+            context: "the JSON config".to_string(),
+            source: Box::new(err),
+        })
+    }
+
+    fn is_toml(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|extension| extension == "toml")
+    }
+}
@@ -1,24 +1,132 @@
+use std::path::PathBuf;
 use std::{fmt, io};
 
+/// A hyperparameter that can't be used for a training
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationError {
+    pub parameter: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.parameter, self.reason)
+    }
+}
+
 #[derive(Debug)]
 pub enum TrainingError {
+    /// No inputs were provided
+    #[deprecated(
+        note = "a missing input count is reported as an `InvalidConfiguration` on `inputs`"
+    )]
     NoInput,
     TempDirError(io::Error),
+    /// The game returned a number of results different from the number of networks
+    FitnessLengthMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// The game returned a number of behaviour descriptors different from the number of networks
+    DescriptorLengthMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// A network got a NaN or infinite fitness with `InvalidFitnessPolicy::Error`
+    InvalidFitness {
+        index: usize,
+        value: f64,
+    },
+    /// Every invalid hyperparameter of the training
+    InvalidConfiguration(Vec<ConfigurationError>),
+    /// The sequences or the samples of a dataset don't fit the network, or are missing
+    InvalidDataset(String),
+    /// Error returned by a `TryGame`
+    Game(Box<dyn std::error::Error + Send + Sync>),
+    /// Failed to serialize or deserialize `context`
+    Serialization {
+        context: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Failed to read or write the history, or another file, on the disk
+    Checkpoint {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Any other I/O error, converted with `?`
+    Io(io::Error),
 }
 
-impl std::error::Error for TrainingError {}
+impl TrainingError {
+    pub(crate) fn game<E>(err: E) -> TrainingError
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        TrainingError::Game(Box::new(err))
+    }
+
+    pub(crate) fn checkpoint(path: impl Into<PathBuf>) -> impl FnOnce(io::Error) -> TrainingError {
+        let path = path.into();
+        move |source| TrainingError::Checkpoint { path, source }
+    }
+}
+
+impl std::error::Error for TrainingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TrainingError::TempDirError(err) => Some(err),
+            TrainingError::Game(err) => Some(&**err),
+            TrainingError::Serialization { source, .. } => Some(&**source),
+            TrainingError::Checkpoint { source, .. } => Some(source),
+            TrainingError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
 impl fmt::Display for TrainingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            #[allow(deprecated)]
             TrainingError::NoInput => write!(f, "No inputs were provided"),
             TrainingError::TempDirError(err) => write!(f, "Failed to create Temp Dir: {:?}", err),
+            TrainingError::FitnessLengthMismatch { expected, got } => write!(
+                f,
+                "Expected one result per network ({} networks), got {} results",
+                expected, got
+            ),
+            TrainingError::DescriptorLengthMismatch { expected, got } => write!(
+                f,
+                "Expected one behaviour descriptor per network ({} networks), got {} descriptors",
+                expected, got
+            ),
+            TrainingError::InvalidFitness { index, value } => {
+                write!(f, "Network {} got an invalid fitness: {}", index, value)
+            }
+            TrainingError::InvalidConfiguration(errors) => write!(
+                f,
+                "Invalid configuration: {}",
+                errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            TrainingError::InvalidDataset(reason) => write!(f, "Invalid dataset: {}", reason),
+            TrainingError::Game(err) => write!(f, "Game error: {}", err),
+            TrainingError::Serialization { context, source } => {
+                write!(f, "Failed to serialize {}: {}", context, source)
+            }
+            TrainingError::Checkpoint { path, source } => {
+                write!(f, "Failed to access {}: {}", path.display(), source)
+            }
+            TrainingError::Io(err) => write!(f, "I/O error: {}", err),
         }
     }
 }
 
 impl From<io::Error> for TrainingError {
     fn from(err: io::Error) -> Self {
-        Self::TempDirError(err)
+        Self::Io(err)
     }
 }
@@ -0,0 +1,133 @@
+use crate::neural_network::NeuralNetwork;
+use crate::topology::{Topology, TopologySmrtPtr};
+use num::Float;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+/// Parameters of the hall of fame, the best genomes ever seen during a training
+///
+/// The hall of fame is given to `Game::post_training_hall_of_fame` at the end of the training
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HallOfFame {
+    /// Maximum number of genomes kept. Defaults to 10
+    pub size: usize,
+    /// Number of the best genomes of the hall of fame added back to every generation when they
+    /// are not in the population anymore. Defaults to 0
+    pub reinjected: usize,
+}
+
+impl HallOfFame {
+    /// # Arguments
+    ///
+    /// `size` - Maximum number of genomes kept
+    pub fn new(size: usize) -> HallOfFame {
+        HallOfFame {
+            size,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of genomes of the hall of fame added back to every generation
+    pub fn reinjected(mut self, count: usize) -> Self {
+        self.reinjected = count;
+        self
+    }
+}
+
+impl Default for HallOfFame {
+    fn default() -> Self {
+        HallOfFame {
+            size: 10,
+            reinjected: 0,
+        }
+    }
+}
+
+/// A genome of the hall of fame
+#[derive(Clone)]
+pub struct HallOfFameMember<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    pub topology: Topology<F>,
+    pub fitness: F,
+    /// Generation in which the genome got this fitness
+    pub generation: usize,
+}
+
+impl<F> HallOfFameMember<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Builds the network of the genome, e.g. to use it as an opponent
+    pub fn network(&self) -> NeuralNetwork<F> {
+        unsafe { NeuralNetwork::new(&self.topology) }
+    }
+}
+
+/// Adds the evaluated topologies to the hall of fame, sorted from the best to the worst
+///
+/// A genome already in the hall of fame, by id or by structure and weights, only updates its
+/// fitness if it improved
+pub(crate) fn update_hall_of_fame<F>(
+    members: &mut Vec<HallOfFameMember<F>>,
+    size: usize,
+    topologies: &[TopologySmrtPtr<F>],
+    results: &[F],
+    generation: usize,
+) where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    for (topology, &fitness) in topologies.iter().zip(results.iter()) {
+        if !fitness.is_finite() {
+            continue;
+        }
+        let is_full = members.len() >= size;
+        if is_full && members.last().is_none_or(|worst| fitness <= worst.fitness) {
+            continue;
+        }
+        let topology = topology.lock().unwrap();
+        let duplicate = members.iter_mut().find(|member| {
+            member.topology.id() == topology.id() || member.topology.same_genome(&topology)
+        });
+        match duplicate {
+            Some(member) => {
+                if fitness > member.fitness {
+                    member.fitness = fitness;
+                    member.generation = generation;
+                }
+            }
+            None => members.push(HallOfFameMember {
+                topology: topology.clone(),
+                fitness,
+                generation,
+            }),
+        }
+        members.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(Ordering::Equal));
+        members.truncate(size);
+    }
+}
+
+/// Merges the halls of fame of several trainings, keeping the `size` best distinct genomes
+pub(crate) fn merge_hall_of_fames<F>(
+    mut members: Vec<HallOfFameMember<F>>,
+    size: usize,
+) -> Vec<HallOfFameMember<F>>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    members.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap_or(Ordering::Equal));
+    let mut merged: Vec<HallOfFameMember<F>> = Vec::new();
+    for member in members {
+        let duplicate = merged.iter().any(|kept| {
+            kept.topology.id() == member.topology.id()
+                || kept.topology.same_genome(&member.topology)
+        });
+        if !duplicate && merged.len() < size {
+            merged.push(member);
+        }
+    }
+    merged
+}
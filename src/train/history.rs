@@ -0,0 +1,414 @@
+use crate::train::error::TrainingError;
+use crate::train::{HistoricTopology, HistoricTopologyDisk, HistoricTopologyLazy};
+use num::Float;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use tempdir::TempDir;
+
+const TEMP_DIR: &str = "temp_history";
+
+/// Stores the best topology of every species of every generation until the end of the training
+///
+/// The default sink keeps the history in memory, or in a temporary directory with
+/// `Train::save_history_to_disk`
+pub trait HistorySink<F>: Send
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Records a generation
+    ///
+    /// # Arguments
+    ///
+    /// `generation` - The index of the generation
+    ///
+    /// `topologies` - The best topology of every species, sorted by species
+    fn record(
+        &mut self,
+        generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError>;
+
+    /// Forgets a recorded generation
+    ///
+    /// # Arguments
+    ///
+    /// `generation` - The index of the generation
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError>;
+
+    /// Returns the recorded history with the generation of every topology, sorted by generation
+    /// and species
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError>;
+
+    /// Forgets the whole history, called when a training starts
+    fn clear(&mut self) -> Result<(), TrainingError>;
+
+    /// Returns the directory the history is written to, if any
+    fn directory(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Keeps the history in memory
+pub struct MemoryHistory<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    history: Vec<HistoricTopology<F>>,
+}
+
+impl<F> MemoryHistory<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    pub fn new() -> MemoryHistory<F> {
+        MemoryHistory {
+            history: Vec::new(),
+        }
+    }
+}
+
+impl<F> Default for MemoryHistory<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> HistorySink<F> for MemoryHistory<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    fn record(
+        &mut self,
+        _generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError> {
+        self.history.extend(topologies);
+        Ok(())
+    }
+
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError> {
+        self.history
+            .retain(|historic| historic.generation != generation);
+        Ok(())
+    }
+
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        let mut history = std::mem::take(&mut self.history);
+        history.sort_by_key(|historic| (historic.generation, historic.species));
+        Ok(history
+            .into_iter()
+            .map(|historic| {
+                (
+                    historic.generation,
+                    HistoricTopologyLazy::Topology(historic),
+                )
+            })
+            .collect())
+    }
+
+    fn clear(&mut self) -> Result<(), TrainingError> {
+        self.history.clear();
+        Ok(())
+    }
+}
+
+/// Writes every topology of the history to its own file, `generation-{g}-species-{s}.json`
+///
+/// The files are read lazily at the end of the training, see `HistoricTopologyLazy`
+pub struct DirectoryHistory {
+    path: PathBuf,
+    // Deletes the directory when dropped if it is temporary
+    _tempdir: Option<TempDir>,
+}
+
+impl DirectoryHistory {
+    /// Writes the history to a directory that is kept after the training, it is created if
+    /// needed. The history files it already contains are deleted when the training starts
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The directory
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<DirectoryHistory, TrainingError> {
+        let path = path.as_ref().to_path_buf();
+        std::fs::create_dir_all(&path).map_err(TrainingError::checkpoint(&path))?;
+        Ok(DirectoryHistory {
+            path,
+            _tempdir: None,
+        })
+    }
+
+    /// Writes the history to a temporary directory, deleted when the sink is dropped
+    pub fn temporary() -> Result<DirectoryHistory, TrainingError> {
+        let tempdir = TempDir::new(TEMP_DIR).map_err(TrainingError::TempDirError)?;
+        Ok(DirectoryHistory {
+            path: tempdir.path().to_path_buf(),
+            _tempdir: Some(tempdir),
+        })
+    }
+
+    fn file_name(generation: usize, species: usize) -> String {
+        format!("generation-{}-species-{}.json", generation, species)
+    }
+
+    /// Parses the generation and the species of a history file name
+    fn parse_file_name(name: &str) -> Option<(usize, usize)> {
+        let name = name.strip_prefix("generation-")?.strip_suffix(".json")?;
+        let (generation, species) = name.split_once("-species-")?;
+        Some((generation.parse().ok()?, species.parse().ok()?))
+    }
+
+    /// Returns the history files sorted by generation and species
+    fn files(&self) -> Result<Vec<(usize, usize, PathBuf)>, TrainingError> {
+        let entries =
+            std::fs::read_dir(&self.path).map_err(TrainingError::checkpoint(&self.path))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(TrainingError::checkpoint(&self.path))?;
+            let parsed = entry.file_name().to_str().and_then(Self::parse_file_name);
+            if let Some((generation, species)) = parsed {
+                files.push((generation, species, entry.path()));
+            }
+        }
+        files.sort_by_key(|(generation, species, _)| (*generation, *species));
+        Ok(files)
+    }
+
+    fn remove_files<P>(&self, mut predicate: P) -> Result<(), TrainingError>
+    where
+        P: FnMut(usize) -> bool,
+    {
+        for (generation, _, path) in self.files()? {
+            if predicate(generation) {
+                std::fs::remove_file(&path).map_err(TrainingError::checkpoint(&path))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F> HistorySink<F> for DirectoryHistory
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    fn record(
+        &mut self,
+        generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError> {
+        for historic in topologies {
+            let path = self
+                .path
+                .join(Self::file_name(generation, historic.species));
+            let disk: HistoricTopologyDisk = historic.into();
+            let serialized =
+                serde_json::to_string(&disk).map_err(|err| TrainingError::Serialization {
+                    context: format!("the history of generation {}", generation),
+                    source: Box::new(err),
+                })?;
+            std::fs::write(&path, serialized).map_err(TrainingError::checkpoint(&path))?;
+        }
+        Ok(())
+    }
+
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError> {
+        self.remove_files(|file_generation| file_generation == generation)
+    }
+
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        self.files()?
+            .into_iter()
+            .map(|(generation, _, path)| {
+                std::fs::File::open(&path)
+                    .map(|file| (generation, HistoricTopologyLazy::Lazy(file)))
+                    .map_err(TrainingError::checkpoint(&path))
+            })
+            .collect()
+    }
+
+    fn clear(&mut self) -> Result<(), TrainingError> {
+        self.remove_files(|_| true)
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        Some(&self.path)
+    }
+}
+
+/// Only records the generations that improved the best fitness recorded so far
+pub struct KeepImprovements<S> {
+    sink: S,
+    best: Option<f64>,
+}
+
+impl<S> KeepImprovements<S> {
+    /// # Arguments
+    ///
+    /// `sink` - The sink receiving the improving generations
+    pub fn new(sink: S) -> KeepImprovements<S> {
+        KeepImprovements { sink, best: None }
+    }
+}
+
+impl<F, S> HistorySink<F> for KeepImprovements<S>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+    S: HistorySink<F>,
+{
+    fn record(
+        &mut self,
+        generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError> {
+        let best = topologies
+            .iter()
+            .filter_map(|historic| historic.get_last_result().to_f64())
+            .filter(|fitness| fitness.is_finite())
+            .fold(None, |best: Option<f64>, fitness| {
+                Some(best.map_or(fitness, |best| best.max(fitness)))
+            });
+        match best {
+            Some(best) if self.best.is_none_or(|recorded| best > recorded) => {
+                self.best = Some(best);
+                self.sink.record(generation, topologies)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError> {
+        self.sink.discard(generation)
+    }
+
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        self.sink.take_history()
+    }
+
+    fn clear(&mut self) -> Result<(), TrainingError> {
+        self.best = None;
+        self.sink.clear()
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        self.sink.directory()
+    }
+}
+
+/// Only keeps the last recorded generations
+pub struct KeepLast<S> {
+    sink: S,
+    count: usize,
+    generations: VecDeque<usize>,
+}
+
+impl<S> KeepLast<S> {
+    /// # Arguments
+    ///
+    /// `sink` - The sink receiving the generations
+    ///
+    /// `count` - The number of generations kept
+    pub fn new(sink: S, count: usize) -> KeepLast<S> {
+        KeepLast {
+            sink,
+            count,
+            generations: VecDeque::new(),
+        }
+    }
+}
+
+impl<F, S> HistorySink<F> for KeepLast<S>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+    S: HistorySink<F>,
+{
+    fn record(
+        &mut self,
+        generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError> {
+        self.sink.record(generation, topologies)?;
+        self.generations.push_back(generation);
+        while self.generations.len() > self.count {
+            if let Some(oldest) = self.generations.pop_front() {
+                self.sink.discard(oldest)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError> {
+        self.generations.retain(|&kept| kept != generation);
+        self.sink.discard(generation)
+    }
+
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        self.generations.clear();
+        self.sink.take_history()
+    }
+
+    fn clear(&mut self) -> Result<(), TrainingError> {
+        self.generations.clear();
+        self.sink.clear()
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        self.sink.directory()
+    }
+}
+
+/// Only records one generation out of `interval`, starting with the first one
+pub struct KeepEveryNth<S> {
+    sink: S,
+    interval: usize,
+}
+
+impl<S> KeepEveryNth<S> {
+    /// # Arguments
+    ///
+    /// `sink` - The sink receiving the generations
+    ///
+    /// `interval` - The number of generations between two recorded generations, at least 1
+    pub fn new(sink: S, interval: usize) -> KeepEveryNth<S> {
+        KeepEveryNth {
+            sink,
+            interval: interval.max(1),
+        }
+    }
+}
+
+impl<F, S> HistorySink<F> for KeepEveryNth<S>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+    S: HistorySink<F>,
+{
+    fn record(
+        &mut self,
+        generation: usize,
+        topologies: Vec<HistoricTopology<F>>,
+    ) -> Result<(), TrainingError> {
+        if generation.is_multiple_of(self.interval) {
+            self.sink.record(generation, topologies)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn discard(&mut self, generation: usize) -> Result<(), TrainingError> {
+        self.sink.discard(generation)
+    }
+
+    fn take_history(&mut self) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        self.sink.take_history()
+    }
+
+    fn clear(&mut self) -> Result<(), TrainingError> {
+        self.sink.clear()
+    }
+
+    fn directory(&self) -> Option<&Path> {
+        self.sink.directory()
+    }
+}
@@ -0,0 +1,333 @@
+use crate::game::TryGame;
+use crate::section;
+use crate::topology::Topology;
+use crate::train::error::TrainingError;
+use crate::train::evolution_number::EvNumber;
+use crate::train::hall_of_fame::merge_hall_of_fames;
+use crate::train::stop_handle::StopHandle;
+use crate::train::{
+    HistoricTopologyLazy, Train, TrainAccessCallback, TrainingOutcome, TrainingStatus,
+};
+use num::Float;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::BorrowMut;
+use std::fmt::Display;
+use std::iter::Sum;
+use std::sync::Arc;
+
+/// Which islands receive the migrants of an island
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationTopology {
+    /// Island `i` sends its migrants to island `i + 1`, the last one to the first one
+    #[default]
+    Ring,
+    /// Every island sends its migrants to every other island
+    FullyConnected,
+}
+
+/// Statistics of an island after one generation
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IslandStatistics<F> {
+    pub island: usize,
+    pub generation: usize,
+    pub best_score: F,
+    pub species_count: usize,
+}
+
+struct Island<'a, T, F, S>
+where
+    F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    T: TryGame<F>,
+    S: BorrowMut<T>,
+{
+    train: Train<'a, T, F, S>,
+    callback: Option<TrainAccessCallback<'a, T, F, S>>,
+    running: bool,
+}
+
+/// Runs several independent populations in parallel and periodically migrates their best
+/// topologies between them
+///
+/// Every island is a `Train` object with its own simulation and hyperparameters, the number of
+/// generations of an island is the number of iterations of its `Train`. The callbacks given to
+/// `access_train_object` are called on the thread calling `start`, once every generation
+pub struct Islands<'a, T, F, S = &'a mut T>
+where
+    F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    T: TryGame<F>,
+    S: BorrowMut<T>,
+{
+    islands: Vec<Island<'a, T, F, S>>,
+    migration_interval_: usize,
+    migrants_: usize,
+    migration_topology_: MigrationTopology,
+    statistics_: Vec<IslandStatistics<F>>,
+    stop_handle_: StopHandle,
+}
+
+impl<'a, T, F, S> Islands<'a, T, F, S>
+where
+    T: TryGame<F>,
+    F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    S: BorrowMut<T> + Send,
+{
+    /// Creates an island model from configured `Train` objects
+    ///
+    /// Default values are:
+    /// - migration_interval -> number of generations between migrations: 10
+    /// - migrants -> number of topologies sent by an island at every migration: 2
+    /// - migration_topology -> Ring
+    ///
+    /// # Arguments
+    ///
+    /// `trains` - One `Train` per island, each with its own simulation
+    pub fn new(trains: Vec<Train<'a, T, F, S>>) -> Islands<'a, T, F, S> {
+        let ev_number = Arc::new(EvNumber::new());
+        let islands = trains
+            .into_iter()
+            .map(|mut train| {
+                train.share_evolution_numbers(ev_number.clone());
+                Island {
+                    callback: train.take_access_train_object(),
+                    train,
+                    running: true,
+                }
+            })
+            .collect();
+        Islands {
+            islands,
+            migration_interval_: 10,
+            migrants_: 2,
+            migration_topology_: MigrationTopology::Ring,
+            statistics_: Vec::new(),
+            stop_handle_: StopHandle::new(),
+        }
+    }
+
+    /// Sets the number of generations between two migrations
+    ///
+    /// # Arguments
+    ///
+    /// `generations` - The number of generations, defaults to 10
+    #[inline]
+    pub fn migration_interval(&mut self, generations: usize) -> &mut Self {
+        self.migration_interval_ = generations.max(1);
+        self
+    }
+
+    /// Sets the number of topologies sent by an island at every migration
+    ///
+    /// # Arguments
+    ///
+    /// `count` - The number of migrants, defaults to 2
+    #[inline]
+    pub fn migrants(&mut self, count: usize) -> &mut Self {
+        self.migrants_ = count;
+        self
+    }
+
+    /// Sets which islands receive the migrants of an island
+    ///
+    /// # Arguments
+    ///
+    /// `topology` - The migration topology, defaults to Ring
+    #[inline]
+    pub fn migration_topology(&mut self, topology: MigrationTopology) -> &mut Self {
+        self.migration_topology_ = topology;
+        self
+    }
+
+    /// Returns the statistics of every island for every generation run so far
+    #[inline]
+    pub fn statistics(&self) -> &[IslandStatistics<F>] {
+        &self.statistics_
+    }
+
+    /// Returns a handle that stops every island after its current generation, the stop request
+    /// is cleared when `start` is called
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle_.clone()
+    }
+
+    /// Returns the number of islands
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.islands.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.islands.is_empty()
+    }
+
+    /// Starts the training of every island.
+    ///
+    /// The histories of all islands are merged, sorted by generation, and given to the
+    /// `post_training` of every island's simulation, so is the merged hall of fame
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub fn start(&mut self) -> Result<TrainingOutcome, TrainingError> {
+        self.stop_handle_.reset();
+        for island in self.islands.iter_mut() {
+            island.train.init()?;
+            island.running = true;
+        }
+        let mut status = TrainingStatus::Completed;
+        while self.islands.iter().any(|island| island.running) {
+            if self.stop_handle_.is_stopped() {
+                log::info!("TRAINING STOPPED");
+                status = TrainingStatus::Cancelled;
+                break;
+            }
+            self.run_epoch()?;
+            self.migrate()?;
+        }
+        if status == TrainingStatus::Completed
+            && self
+                .islands
+                .iter()
+                .all(|island| island.train.species_count() == 0)
+        {
+            status = TrainingStatus::Extinct;
+        }
+        let generations = self
+            .islands
+            .iter()
+            .map(|island| island.train.generation())
+            .max()
+            .unwrap_or(0);
+        section!();
+        log::info!("POST TRAINING");
+        let mut history: Vec<(usize, usize, HistoricTopologyLazy<F>)> = Vec::new();
+        for (index, island) in self.islands.iter_mut().enumerate() {
+            for (generation, historic) in island.train.take_history_with_generations()? {
+                history.push((generation, index, historic));
+            }
+        }
+        history.sort_by_key(|(generation, _, _)| *generation);
+        let mut hall_of_fame = Vec::new();
+        let mut hall_of_fame_size = 0;
+        for island in self.islands.iter_mut() {
+            if let Some(hall) = &island.train.config().hall_of_fame {
+                hall_of_fame_size = hall_of_fame_size.max(hall.size);
+            }
+            hall_of_fame.extend(island.train.take_hall_of_fame().unwrap_or_default());
+        }
+        let hall_of_fame = merge_hall_of_fames(hall_of_fame, hall_of_fame_size);
+        for island in 0..self.islands.len() {
+            let mut island_history = Vec::with_capacity(history.len());
+            for (_, source, historic) in &history {
+                let source_dir = self.islands[*source]
+                    .train
+                    .history_dir()
+                    .map(|dir| dir.to_path_buf())
+                    .unwrap_or_default();
+                island_history.push(
+                    historic
+                        .try_clone()
+                        .map_err(TrainingError::checkpoint(source_dir))?,
+                );
+            }
+            let simulation = self.islands[island].train.simulation.borrow_mut();
+            simulation
+                .try_post_training(island_history)
+                .map_err(TrainingError::game)?;
+            if hall_of_fame_size > 0 {
+                simulation
+                    .try_post_training_hall_of_fame(hall_of_fame.clone())
+                    .map_err(TrainingError::game)?;
+            }
+        }
+        Ok(TrainingOutcome {
+            status,
+            generations,
+        })
+    }
+
+    /// Runs every island until the next migration
+    fn run_epoch(&mut self) -> Result<(), TrainingError> {
+        for _ in 0..self.migration_interval_ {
+            if self.stop_handle_.is_stopped() {
+                break;
+            }
+            let mut trains: Vec<(usize, &mut Train<'a, T, F, S>)> = self
+                .islands
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, island)| island.running)
+                .map(|(index, island)| (index, &mut island.train))
+                .collect();
+            let reports = cond_iter_mut!(trains)
+                .map(|(index, train)| {
+                    let report = train.step()?;
+                    let statistics = IslandStatistics {
+                        island: *index,
+                        generation: report.generation,
+                        best_score: report.best_score,
+                        species_count: report.species_count,
+                    };
+                    Ok((statistics, report.extinct))
+                })
+                .collect::<Vec<Result<(IslandStatistics<F>, bool), TrainingError>>>();
+            for report in reports {
+                let (statistics, extinct) = report?;
+                let island = &mut self.islands[statistics.island];
+                island.running =
+                    !extinct && island.train.generation() < island.train.iterations_count();
+                if let (Some(callback), false) = (&mut island.callback, extinct) {
+                    callback(&mut island.train);
+                    // As in `Train::step`, a callback given from the callback is dropped
+                    island.train.take_access_train_object();
+                }
+                self.statistics_.push(statistics);
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the best topologies of every running island to its running neighbours, the migrants
+    /// replace their worst members
+    fn migrate(&mut self) -> Result<(), TrainingError> {
+        let island_count = self.islands.len();
+        if island_count < 2 || self.migrants_ == 0 {
+            return Ok(());
+        }
+        // Islands that stopped running don't send migrants anymore
+        let emigrants: Vec<Vec<Topology<F>>> = self
+            .islands
+            .iter()
+            .map(|island| {
+                if island.running {
+                    island.train.emigrants(self.migrants_)
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect();
+        let mut immigrants: Vec<Vec<Topology<F>>> = vec![Vec::new(); island_count];
+        for (source, topologies) in emigrants.into_iter().enumerate() {
+            match self.migration_topology_ {
+                MigrationTopology::Ring => {
+                    immigrants[(source + 1) % island_count].extend(topologies);
+                }
+                MigrationTopology::FullyConnected => {
+                    for (destination, received) in immigrants.iter_mut().enumerate() {
+                        if destination != source {
+                            received.extend(topologies.iter().cloned());
+                        }
+                    }
+                }
+            }
+        }
+        for (island, topologies) in self.islands.iter_mut().zip(immigrants) {
+            if island.running {
+                log::info!("MIGRATION: {} topologies received", topologies.len());
+                island.train.immigrate(topologies)?;
+            }
+        }
+        Ok(())
+    }
+}
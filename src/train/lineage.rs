@@ -0,0 +1,185 @@
+use crate::topology::MutationOperation;
+use crate::train::error::TrainingError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+/// How a genome was created, recorded when it is evaluated for the first time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LineageRecord {
+    pub id: u64,
+    /// Empty for the first generation
+    pub parents: Vec<u64>,
+    pub birth_generation: usize,
+    pub species_id: usize,
+    /// Operations applied to the parents to create the genome
+    pub operations: Vec<MutationOperation>,
+    /// Number of genes
+    pub genome_size: usize,
+    /// Fitness of the last evaluation of the genome
+    pub fitness: Option<f64>,
+}
+
+/// Every genome evaluated during a training with `Train::track_lineage`
+#[derive(Clone, Debug, Default)]
+pub struct LineageLog {
+    records: Vec<LineageRecord>,
+    indices: HashMap<u64, usize>,
+}
+
+impl LineageLog {
+    pub fn new() -> LineageLog {
+        Self::default()
+    }
+
+    /// Returns the records in the order the genomes were born
+    pub fn records(&self) -> &[LineageRecord] {
+        &self.records
+    }
+
+    /// Returns the record of a genome
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id of the genome, see `Topology::id`
+    pub fn get(&self, id: u64) -> Option<&LineageRecord> {
+        self.indices.get(&id).map(|&index| &self.records[index])
+    }
+
+    /// Returns the record of a genome followed by the records of all its ancestors, the most
+    /// recent first
+    ///
+    /// # Arguments
+    ///
+    /// `id` - The id of the genome, e.g. `HistoricTopology::id` of the best topology
+    pub fn ancestry(&self, id: u64) -> Vec<&LineageRecord> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![id];
+        let mut ancestry = Vec::new();
+        while let Some(id) = pending.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(record) = self.get(id) {
+                pending.extend(record.parents.iter().copied());
+                ancestry.push(record);
+            }
+        }
+        ancestry.sort_by(|a, b| {
+            b.birth_generation
+                .cmp(&a.birth_generation)
+                .then(b.id.cmp(&a.id))
+        });
+        ancestry
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.records.clear();
+        self.indices.clear();
+    }
+
+    pub(crate) fn contains(&self, id: u64) -> bool {
+        self.indices.contains_key(&id)
+    }
+
+    pub(crate) fn push(&mut self, record: LineageRecord) {
+        self.indices.insert(record.id, self.records.len());
+        self.records.push(record);
+    }
+
+    pub(crate) fn set_fitness(&mut self, id: u64, fitness: f64) {
+        if let Some(&index) = self.indices.get(&id) {
+            self.records[index].fitness = Some(fitness);
+        }
+    }
+
+    /// Returns the records of `root` and its ancestors, or every record
+    fn selection(&self, root: Option<u64>) -> Vec<&LineageRecord> {
+        match root {
+            Some(root) => {
+                let mut ancestry = self.ancestry(root);
+                ancestry.reverse();
+                ancestry
+            }
+            None => self.records.iter().collect(),
+        }
+    }
+
+    /// Writes one JSON record per line
+    ///
+    /// # Arguments
+    ///
+    /// `root` - Only writes the ancestry of this genome if set
+    pub fn to_jsonl(&self, root: Option<u64>) -> Result<String, TrainingError> {
+        let mut jsonl = String::new();
+        for record in self.selection(root) {
+            let line =
+                serde_json::to_string(record).map_err(|err| TrainingError::Serialization {
+                    context: format!("the lineage of genome {}", record.id),
+                    source: Box::new(err),
+                })?;
+            jsonl.push_str(&line);
+            jsonl.push('\n');
+        }
+        Ok(jsonl)
+    }
+
+    /// Writes a Graphviz graph, with an edge from each parent to its children labelled by the
+    /// operations that created the child
+    ///
+    /// # Arguments
+    ///
+    /// `root` - Only writes the ancestry of this genome if set
+    pub fn to_dot(&self, root: Option<u64>) -> String {
+        let mut dot = String::from("digraph lineage {\n    rankdir=LR;\n");
+        let records = self.selection(root);
+        let written: HashSet<u64> = records.iter().map(|record| record.id).collect();
+        for record in &records {
+            let fitness = record
+                .fitness
+                .map_or("-".to_string(), |fitness| format!("{:.4}", fitness));
+            let _ = writeln!(
+                dot,
+                "    g{} [label=\"{}\\ngeneration {}\\nfitness {}\\ngenes {}\"];",
+                record.id, record.id, record.birth_generation, fitness, record.genome_size
+            );
+        }
+        for record in &records {
+            let operations: Vec<String> = record
+                .operations
+                .iter()
+                .map(|operation| format!("{:?}", operation))
+                .collect();
+            for parent in record.parents.iter().filter(|id| written.contains(id)) {
+                let _ = writeln!(
+                    dot,
+                    "    g{} -> g{} [label=\"{}\"];",
+                    parent,
+                    record.id,
+                    operations.join(", ")
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the log to a file, as a Graphviz graph if its extension is `.dot`, in JSON lines
+    /// otherwise
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the file
+    ///
+    /// `root` - Only writes the ancestry of this genome if set
+    pub fn save<P: AsRef<Path>>(&self, path: P, root: Option<u64>) -> Result<(), TrainingError> {
+        let path = path.as_ref();
+        let content = if path.extension().is_some_and(|extension| extension == "dot") {
+            self.to_dot(root)
+        } else {
+            self.to_jsonl(root)?
+        };
+        std::fs::write(path, content).map_err(TrainingError::checkpoint(path))
+    }
+}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// How the weights are tuned by the local search
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LocalSearchMethod {
+    /// Keeps the perturbed weights if they improve the fitness, with a constant step size
+    #[default]
+    HillClimbing,
+    /// (1+1)-ES, the step size grows after an improvement and shrinks otherwise, following the
+    /// one fifth success rule
+    OnePlusOne,
+}
+
+impl LocalSearchMethod {
+    /// Returns the step size of the next step
+    pub(crate) fn next_step_size(&self, step_size: f64, improved: bool) -> f64 {
+        match self {
+            LocalSearchMethod::HillClimbing => step_size,
+            LocalSearchMethod::OnePlusOne if improved => step_size * 1.5,
+            LocalSearchMethod::OnePlusOne => step_size * 1.5f64.powf(-0.25),
+        }
+    }
+}
+
+/// Parameters of the local search run on the weights of the best genomes of every species
+/// between two generations
+///
+/// The perturbed weights are evaluated with `Game::run_generation`, given only the perturbed
+/// networks, and the improvements are written back into the genomes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocalSearch {
+    pub method: LocalSearchMethod,
+    /// Number of the best genomes of every species tuned. Defaults to 1
+    pub genomes_per_species: usize,
+    /// Number of steps per genome. Defaults to 10
+    pub steps: usize,
+    /// Initial standard deviation of the weight perturbations. Defaults to 0.1
+    pub step_size: f64,
+    /// Maximum number of evaluations per generation. Defaults to 100
+    pub budget: usize,
+}
+
+impl LocalSearch {
+    /// # Arguments
+    ///
+    /// `method` - Hill climbing or (1+1)-ES
+    pub fn new(method: LocalSearchMethod) -> LocalSearch {
+        LocalSearch {
+            method,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of the best genomes of every species tuned
+    pub fn genomes_per_species(mut self, count: usize) -> Self {
+        self.genomes_per_species = count;
+        self
+    }
+
+    /// Sets the number of steps per genome
+    pub fn steps(mut self, steps: usize) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Sets the initial standard deviation of the weight perturbations
+    pub fn step_size(mut self, step_size: f64) -> Self {
+        self.step_size = step_size;
+        self
+    }
+
+    /// Sets the maximum number of evaluations per generation
+    pub fn budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self
+    }
+}
+
+impl Default for LocalSearch {
+    fn default() -> Self {
+        LocalSearch {
+            method: LocalSearchMethod::HillClimbing,
+            genomes_per_species: 1,
+            steps: 10,
+            step_size: 0.1,
+            budget: 100,
+        }
+    }
+}
@@ -1,6 +1,17 @@
+pub mod config;
 pub mod error;
 pub mod evolution_number;
+pub mod hall_of_fame;
+pub mod history;
+pub mod islands;
+pub mod lineage;
+pub mod local_search;
+pub mod noise;
+pub mod novelty;
+pub mod pareto;
+pub mod speciation;
 mod species;
+pub mod stop_handle;
 mod training;
 
 pub use training::*;
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+/// Parameters of the fitness re-evaluation of noisy games
+///
+/// The fitness of a genome is averaged over all its evaluations, including the ones of its
+/// elite copies in the following generations. The best genomes of every generation are
+/// evaluated again `reevaluations` times with `Game::run_generation`, and a new best historical
+/// score is only recorded once the genome reaching it was re-evaluated
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseHandling {
+    /// Number of additional evaluations of the elites and the candidate-best genomes. Defaults
+    /// to 3
+    pub reevaluations: usize,
+    /// Number of the best genomes of every generation re-evaluated besides the elites.
+    /// Defaults to 3
+    pub candidates: usize,
+}
+
+impl NoiseHandling {
+    /// # Arguments
+    ///
+    /// `reevaluations` - Number of additional evaluations of the best genomes
+    pub fn new(reevaluations: usize) -> NoiseHandling {
+        NoiseHandling {
+            reevaluations,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the number of the best genomes of every generation re-evaluated besides the elites
+    pub fn candidates(mut self, count: usize) -> Self {
+        self.candidates = count;
+        self
+    }
+}
+
+impl Default for NoiseHandling {
+    fn default() -> Self {
+        NoiseHandling {
+            reevaluations: 3,
+            candidates: 3,
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::train::error::TrainingError;
+use num::Float;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::path::Path;
+
+/// Name of the file the archive is written to in the history directory, see `Train::history_dir`
+pub const ARCHIVE_FILE: &str = "novelty-archive.json";
+
+/// Parameters of novelty search
+///
+/// The novelty of an individual is the mean euclidean distance between its behaviour descriptor and
+/// the `k` nearest descriptors among the current generation and the archive.
+///
+/// The score used for selection is `(1 - novelty_weight) * fitness + novelty_weight * novelty`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NoveltySearch<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Number of nearest neighbours, defaults to 15
+    pub k: usize,
+    /// Weight of the novelty in the selection score, 1 means pure novelty search. Defaults to 1
+    pub novelty_weight: F,
+    /// Novelty above which a behaviour is added to the archive. Defaults to 1
+    pub archive_threshold: F,
+    /// Maximum number of behaviours in the archive, the oldest ones are dropped first. Defaults to 1000
+    pub max_archive_size: usize,
+}
+
+impl<F> NoveltySearch<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    /// # Arguments
+    ///
+    /// `k` - Number of nearest neighbours
+    ///
+    /// `novelty_weight` - Weight of the novelty in the selection score, between 0 and 1
+    pub fn new(k: usize, novelty_weight: F) -> NoveltySearch<F> {
+        NoveltySearch {
+            k,
+            novelty_weight,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the novelty above which a behaviour is added to the archive
+    pub fn archive_threshold(mut self, threshold: F) -> Self {
+        self.archive_threshold = threshold;
+        self
+    }
+
+    /// Sets the maximum number of behaviours kept in the archive
+    pub fn max_archive_size(mut self, size: usize) -> Self {
+        self.max_archive_size = size;
+        self
+    }
+
+    /// Blends fitness and novelty into the selection score
+    pub fn blend(&self, fitness: F, novelty: F) -> F {
+        (F::one() - self.novelty_weight) * fitness + self.novelty_weight * novelty
+    }
+}
+
+impl<F> Default for NoveltySearch<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    fn default() -> Self {
+        NoveltySearch {
+            k: 15,
+            novelty_weight: F::one(),
+            archive_threshold: F::one(),
+            max_archive_size: 1000,
+        }
+    }
+}
+
+/// Behaviours that were novel when they were first seen
+///
+/// Written to `ARCHIVE_FILE` in the history directory after every generation, a training can
+/// start again from it with `Train::restore_novelty_archive`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoveltyArchive<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    behaviours: Vec<Vec<F>>,
+}
+
+impl<F> NoveltyArchive<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    pub fn new() -> NoveltyArchive<F> {
+        NoveltyArchive {
+            behaviours: Vec::new(),
+        }
+    }
+
+    /// Returns the archived behaviour descriptors, oldest first
+    pub fn behaviours(&self) -> &[Vec<F>] {
+        &self.behaviours
+    }
+
+    pub fn len(&self) -> usize {
+        self.behaviours.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.behaviours.is_empty()
+    }
+
+    /// Reads an archive written by `save`
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the archive, e.g. `ARCHIVE_FILE` in the history directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<NoveltyArchive<F>, TrainingError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path).map_err(TrainingError::checkpoint(path))?;
+        let behaviours: Vec<Vec<f64>> =
+            serde_json::from_str(&content).map_err(|err| TrainingError::Serialization {
+                context: format!("the novelty archive {}", path.display()),
+                source: Box::new(err),
+            })?;
+        Ok(NoveltyArchive {
+            behaviours: behaviours
+                .into_iter()
+                .map(|behaviour| behaviour.into_iter().map(|x| F::from(x).unwrap()).collect())
+                .collect(),
+        })
+    }
+
+    /// Writes the archive to a JSON file
+    ///
+    /// # Arguments
+    ///
+    /// `path` - The path of the archive
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TrainingError> {
+        let path = path.as_ref();
+        let behaviours: Vec<Vec<f64>> = self
+            .behaviours
+            .iter()
+            .map(|behaviour| behaviour.iter().map(|x| x.to_f64().unwrap()).collect())
+            .collect();
+        let content =
+            serde_json::to_string(&behaviours).map_err(|err| TrainingError::Serialization {
+                context: "the novelty archive".to_string(),
+                source: Box::new(err),
+            })?;
+        std::fs::write(path, content).map_err(TrainingError::checkpoint(path))
+    }
+
+    /// Computes the novelty of every behaviour of a generation and archives the novel ones
+    ///
+    /// # Arguments
+    ///
+    /// `config` - The novelty search parameters
+    ///
+    /// `behaviours` - One behaviour descriptor per individual
+    pub fn evaluate(&mut self, config: &NoveltySearch<F>, behaviours: &[Vec<F>]) -> Vec<F> {
+        let novelties: Vec<F> = behaviours
+            .iter()
+            .enumerate()
+            .map(|(index, behaviour)| {
+                let mut distances: Vec<F> = behaviours
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_index, _)| *other_index != index)
+                    .map(|(_, other)| other)
+                    .chain(self.behaviours.iter())
+                    .map(|other| Self::distance(behaviour, other))
+                    .collect();
+                if distances.is_empty() {
+                    return F::zero();
+                }
+                distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                let k = config.k.max(1).min(distances.len());
+                distances.iter().take(k).fold(F::zero(), |acc, &d| acc + d) / F::from(k).unwrap()
+            })
+            .collect();
+        for (behaviour, novelty) in behaviours.iter().zip(novelties.iter()) {
+            if *novelty > config.archive_threshold {
+                self.behaviours.push(behaviour.clone());
+            }
+        }
+        if self.behaviours.len() > config.max_archive_size {
+            let overflow = self.behaviours.len() - config.max_archive_size;
+            self.behaviours.drain(..overflow);
+        }
+        novelties
+    }
+
+    fn distance(first: &[F], second: &[F]) -> F {
+        first
+            .iter()
+            .zip(second.iter())
+            .fold(F::zero(), |acc, (&a, &b)| acc + (a - b) * (a - b))
+            .sqrt()
+    }
+}
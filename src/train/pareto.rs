@@ -0,0 +1,134 @@
+use crate::topology::Topology;
+use num::Float;
+use std::cmp::Ordering;
+use std::fmt::Display;
+
+/// A member of the Pareto front of a multi-objective training
+#[derive(Clone)]
+pub struct ParetoMember<F>
+where
+    F: Float + std::ops::AddAssign + Display + Send,
+{
+    pub topology: Topology<F>,
+    /// The objectives of the topology, including the complexity objective if enabled
+    pub objectives: Vec<F>,
+    pub generation: usize,
+}
+
+/// Returns true if `first` is at least as good as `second` on every objective and better on one
+///
+/// All objectives are maximised
+pub fn dominates<F: Float>(first: &[F], second: &[F]) -> bool {
+    let mut strictly_better = false;
+    for (&a, &b) in first.iter().zip(second.iter()) {
+        if a < b {
+            return false;
+        }
+        if a > b {
+            strictly_better = true;
+        }
+    }
+    strictly_better
+}
+
+/// Sorts individuals into successive non-dominated fronts, as in NSGA-II
+///
+/// Returns the indices of the individuals of each front, the first front being the Pareto front
+///
+/// # Arguments
+///
+/// `objectives` - The objectives of every individual, all maximised
+pub fn non_dominated_sort<F: Float>(objectives: &[Vec<F>]) -> Vec<Vec<usize>> {
+    let len = objectives.len();
+    let mut dominated_by: Vec<Vec<usize>> = vec![Vec::new(); len];
+    let mut domination_count = vec![0usize; len];
+    let mut fronts: Vec<Vec<usize>> = vec![Vec::new()];
+    for p in 0..len {
+        for q in 0..len {
+            if p == q {
+                continue;
+            }
+            if dominates(&objectives[p], &objectives[q]) {
+                dominated_by[p].push(q);
+            } else if dominates(&objectives[q], &objectives[p]) {
+                domination_count[p] += 1;
+            }
+        }
+        if domination_count[p] == 0 {
+            fronts[0].push(p);
+        }
+    }
+    let mut current = 0;
+    while !fronts[current].is_empty() {
+        let mut next = Vec::new();
+        for &p in &fronts[current] {
+            for &q in &dominated_by[p] {
+                domination_count[q] -= 1;
+                if domination_count[q] == 0 {
+                    next.push(q);
+                }
+            }
+        }
+        current += 1;
+        fronts.push(next);
+    }
+    fronts.pop();
+    fronts
+}
+
+/// Computes the crowding distance of the individuals of a front, as in NSGA-II
+///
+/// The individuals at the boundaries of an objective get an infinite distance
+///
+/// # Arguments
+///
+/// `objectives` - The objectives of every individual
+///
+/// `front` - The indices of the individuals in the front
+pub fn crowding_distance<F: Float>(objectives: &[Vec<F>], front: &[usize]) -> Vec<F> {
+    let mut distances = vec![F::zero(); front.len()];
+    if front.len() <= 2 {
+        return vec![F::infinity(); front.len()];
+    }
+    let objective_count = objectives[front[0]].len();
+    let mut order: Vec<usize> = (0..front.len()).collect();
+    (0..objective_count).for_each(|objective| {
+        let value = |position: usize| objectives[front[position]][objective];
+        order.sort_by(|&a, &b| value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal));
+        let min = value(order[0]);
+        let max = value(order[order.len() - 1]);
+        distances[order[0]] = F::infinity();
+        distances[order[order.len() - 1]] = F::infinity();
+        let range = max - min;
+        if range <= F::zero() {
+            return;
+        }
+        for i in 1..order.len() - 1 {
+            distances[order[i]] =
+                distances[order[i]] + (value(order[i + 1]) - value(order[i - 1])) / range;
+        }
+    });
+    distances
+}
+
+/// Turns objectives into a scalar fitness preserving the NSGA-II crowded comparison
+///
+/// Individuals of a better front always get a higher fitness, ties inside a front are broken by
+/// the crowding distance. Returns the fitness and the fronts
+pub fn pareto_fitness<F: Float>(objectives: &[Vec<F>]) -> (Vec<F>, Vec<Vec<usize>>) {
+    let fronts = non_dominated_sort(objectives);
+    let mut fitness = vec![F::zero(); objectives.len()];
+    let front_count = fronts.len();
+    for (rank, front) in fronts.iter().enumerate() {
+        let distances = crowding_distance(objectives, front);
+        for (&index, &distance) in front.iter().zip(distances.iter()) {
+            let crowding = if distance.is_infinite() {
+                F::from(0.99).unwrap()
+            } else {
+                F::from(0.9).unwrap() * distance / (F::one() + distance)
+            };
+            fitness[index] = F::from(front_count - rank).unwrap() + crowding;
+        }
+    }
+    (fitness, fronts)
+}
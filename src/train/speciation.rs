@@ -0,0 +1,82 @@
+use crate::topology::Topology;
+use num::Float;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Genomic distance used to decide whether two topologies belong to the same species
+///
+/// Two topologies are put in the same species if their distance is lower or equal to the delta threshold
+pub trait CompatibilityMetric<T>: Send + Sync
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// Returns the distance between two topologies
+    ///
+    /// # Arguments
+    ///
+    /// `top1` - The topology being assigned to a species
+    ///
+    /// `top2` - The representative of the species
+    fn distance(&self, top1: &Topology<T>, top2: &Topology<T>) -> T;
+}
+
+/// The compatibility formula from the NEAT paper, used by default
+///
+/// `delta = (c1 * disjoints + c2 * excess) / n + (mean(weight_distances) * c3)`
+///
+/// Where `n` is the size of the larger topology if it has more than 20 genes, 1 otherwise
+#[derive(Clone, Debug)]
+pub struct NeatCompatibility<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    pub c1: T,
+    pub c2: T,
+    pub c3: T,
+}
+
+impl<T> NeatCompatibility<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    /// # Arguments
+    ///
+    /// `c1` - Weight of the disjoint genes
+    ///
+    /// `c2` - Weight of the excess genes
+    ///
+    /// `c3` - Weight of the mean weight difference of the common genes
+    pub fn new(c1: T, c2: T, c3: T) -> NeatCompatibility<T> {
+        NeatCompatibility { c1, c2, c3 }
+    }
+}
+
+impl<T> Default for NeatCompatibility<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send,
+{
+    fn default() -> Self {
+        NeatCompatibility::new(T::one(), T::one(), T::one())
+    }
+}
+
+impl<T> CompatibilityMetric<T> for NeatCompatibility<T>
+where
+    T: Float + std::ops::AddAssign + Display + Send + Sync,
+{
+    fn distance(&self, top1: &Topology<T>, top2: &Topology<T>) -> T {
+        Topology::delta_compatibility(top1, top2, self.c1, self.c2, self.c3)
+    }
+}
+
+/// Topology of a species that new topologies are compared to when speciating
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeciesRepresentative {
+    /// The best topology of the last generation, default
+    #[default]
+    Best,
+    /// A random member of the new generation of the species
+    Random,
+    /// A random member of the previous generation of the species, as in the NEAT paper
+    PreviousGenerationRandom,
+}
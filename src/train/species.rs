@@ -1,23 +1,29 @@
 use crate::topology::mutation_probabilities::MutationProbabilities;
 use crate::topology::{Topology, TopologySmrtPtr};
 use crate::train::evolution_number::EvNumber;
+use crate::train::speciation::SpeciesRepresentative;
 use num::Float;
-use rand::prelude::ThreadRng;
+use rand::prelude::{SliceRandom, ThreadRng};
 use rand::{thread_rng, Rng};
 use std::fmt::Display;
 use std::iter::Sum;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+static NEXT_SPECIES_ID: AtomicUsize = AtomicUsize::new(0);
+
 pub struct Species<T>
 where
     T: Float + Sum + std::ops::AddAssign + Display + Send,
 {
     pub topologies: Vec<TopologySmrtPtr<T>>,
     pub best_topology: Topology<T>,
+    pub representative: Topology<T>,
     best_historical_score: T,
     pub stagnation_counter: u8,
     pub adjusted_fitness: T,
     pub max_topologies: usize,
+    id: usize,
 }
 
 impl<T> Species<T>
@@ -25,13 +31,16 @@ where
     T: Float + Sum + std::ops::AddAssign + Display + Send,
 {
     pub fn new(first_topology: TopologySmrtPtr<T>) -> Species<T> {
+        let best_topology = first_topology.lock().unwrap().clone();
         Species {
-            topologies: vec![first_topology.clone()],
-            best_topology: first_topology.lock().unwrap().clone(),
+            topologies: vec![first_topology],
+            representative: best_topology.clone(),
+            best_topology,
             best_historical_score: T::zero(),
             stagnation_counter: 0,
             adjusted_fitness: T::zero(),
             max_topologies: 0,
+            id: NEXT_SPECIES_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -60,11 +69,13 @@ where
         let best_topology = topologies.last().unwrap().lock().unwrap().clone();
         Species {
             topologies,
+            representative: best_topology.clone(),
             best_topology,
             best_historical_score: T::zero(),
             stagnation_counter: 0,
             adjusted_fitness: T::zero(),
             max_topologies: 0,
+            id: NEXT_SPECIES_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -89,11 +100,13 @@ where
         let best_topology = topologies.last().unwrap().lock().unwrap().clone();
         Species {
             topologies,
+            representative: best_topology.clone(),
             best_topology,
             best_historical_score: T::zero(),
             stagnation_counter: 0,
             adjusted_fitness: T::zero(),
             max_topologies: 0,
+            id: NEXT_SPECIES_ID.fetch_add(1, Ordering::Relaxed),
         }
     }
 
@@ -102,6 +115,7 @@ where
         ev_number: Arc<EvNumber>,
         proba: MutationProbabilities,
         run_crossovers: bool,
+        representative: SpeciesRepresentative,
     ) {
         self.topologies.sort_by(|top1, top2| {
             let top1_borrow = &**top1;
@@ -113,18 +127,27 @@ where
                 .unwrap()
         });
         let best_topology = self.topologies.last().unwrap();
-        let last_result = {
+        {
             let top_borrow = &**best_topology;
             let best_top = &*top_borrow.lock().unwrap();
             self.best_topology = best_top.clone();
-            best_top.get_last_result()
-        };
-        if last_result > self.best_historical_score {
-            self.best_historical_score = last_result;
+        }
+        // Stagnation is measured on the raw fitness, not on the selection score
+        let best_objective = self
+            .topologies
+            .iter()
+            .map(|top| top.lock().unwrap().objective())
+            .fold(T::neg_infinity(), T::max);
+        if best_objective > self.best_historical_score {
+            self.best_historical_score = best_objective;
             self.stagnation_counter = 0;
         } else {
             self.stagnation_counter += 1;
         }
+        self.representative = match representative {
+            SpeciesRepresentative::PreviousGenerationRandom => self.random_member(),
+            _ => self.best_topology.clone(),
+        };
         self.do_selection(ev_number, proba, run_crossovers);
     }
 
@@ -199,6 +222,26 @@ where
         new_topologies
     }
 
+    /// Replaces the representative by a random member of the current topologies
+    pub fn pick_random_representative(&mut self) {
+        if !self.topologies.is_empty() {
+            self.representative = self.random_member();
+        }
+    }
+
+    fn random_member(&self) -> Topology<T> {
+        let mut rng = thread_rng();
+        match self.topologies.choose(&mut rng) {
+            Some(top) => top.lock().unwrap().clone(),
+            None => self.best_topology.clone(),
+        }
+    }
+
+    /// Returns the id of the species, unique in the process
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
     pub fn push(&mut self, top: TopologySmrtPtr<T>) {
         self.topologies.push(top);
     }
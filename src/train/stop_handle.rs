@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Thread-safe handle used to stop a running training cleanly
+///
+/// The training stops after the current generation and still calls `post_training` with the
+/// history so far. Obtained with `Train::stop_handle`, it can be cloned and moved to another
+/// thread or to a Ctrl-C handler
+#[derive(Clone, Debug, Default)]
+pub struct StopHandle {
+    stopped: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    pub fn new() -> StopHandle {
+        Self::default()
+    }
+
+    /// Asks the training to stop after the current generation
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if `stop` was called since the creation or the last `reset`
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Clears the stop request so that the training can be started again
+    pub fn reset(&self) {
+        self.stopped.store(false, Ordering::SeqCst);
+    }
+}
@@ -1,56 +1,41 @@
-use crate::game::{Game, GameAsync};
+use crate::game::{GameAsync, MultiObjectiveGame, NetworkInfo, TryGame};
 #[cfg(target_arch = "wasm32")]
 use crate::instant_wasm_replacement::Instant;
 use crate::neural_network::NeuralNetwork;
 use crate::section;
 use crate::topology::mutation_probabilities::MutationProbabilities;
-use crate::topology::{Topology, TopologySmrtPtr};
+use crate::topology::{MutationOperation, Topology, TopologySmrtPtr};
+use crate::train::config::TrainConfig;
 use crate::train::error::TrainingError;
 use crate::train::evolution_number::EvNumber;
+use crate::train::hall_of_fame::{update_hall_of_fame, HallOfFame, HallOfFameMember};
+use crate::train::history::{DirectoryHistory, HistorySink, MemoryHistory};
+use crate::train::lineage::{LineageLog, LineageRecord};
+use crate::train::local_search::LocalSearch;
+use crate::train::noise::NoiseHandling;
+use crate::train::novelty::{NoveltyArchive, NoveltySearch, ARCHIVE_FILE};
+use crate::train::pareto::{pareto_fitness, ParetoMember};
+use crate::train::speciation::{CompatibilityMetric, NeatCompatibility, SpeciesRepresentative};
 use crate::train::species::Species;
+use crate::train::stop_handle::StopHandle;
 use itertools::Itertools;
 use num::Float;
+use rand::thread_rng;
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::BorrowMut;
+use std::collections::HashSet;
 use std::fmt::Display;
-use std::fs::File;
-use std::io::{self, BufReader, Write};
+use std::io::{self, BufReader, Seek, SeekFrom};
 use std::iter::Sum;
 use std::sync::{Arc, Mutex};
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
-use tempdir::TempDir;
+const CONFIG_FILE: &str = "config.json";
 
-macro_rules! cond_iter {
-    ($collection: expr) => {{
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            $collection.par_iter()
-        }
-        #[cfg(target_arch = "wasm32")]
-        {
-            $collection.iter()
-        }
-    }};
-}
-
-macro_rules! cond_iter_mut {
-    ($collection: expr) => {{
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            $collection.par_iter_mut()
-        }
-        #[cfg(target_arch = "wasm32")]
-        {
-            $collection.iter_mut()
-        }
-    }};
-}
-
-const TEMP_DIR: &str = "temp_history";
-
-pub type TrainAccessCallback<'a, T, F> = Box<dyn FnMut(&mut Train<'a, T, F>)>;
+pub type TrainAccessCallback<'a, T, F, S = &'a mut T> =
+    Box<dyn FnMut(&mut Train<'a, T, F, S>) + Send>;
 
 #[derive(Clone)]
 pub struct HistoricTopology<F>
@@ -59,6 +44,8 @@ where
 {
     pub topology: Topology<F>,
     pub generation: usize,
+    /// Rank of the species in its generation, from the worst to the best
+    pub species: usize,
 }
 
 impl<F> std::ops::Deref for HistoricTopology<F>
@@ -72,6 +59,7 @@ where
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum HistoricTopologyLazy<F>
 where
     F: Float + std::ops::AddAssign + Display + Send,
@@ -92,7 +80,8 @@ where
             Lazy(file) => file,
             Topology(topology) => return Ok(topology.clone()),
         };
-        let reader = BufReader::new(file);
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(0))?;
 
         let topology = if let Ok(top) = serde_json::from_reader::<_, HistoricTopologyDisk>(reader) {
             top.into()
@@ -130,12 +119,25 @@ where
     pub fn is_loaded(&self) -> bool {
         matches!(self, Self::Topology(_))
     }
+
+    /// Copies the topology, or opens the same file again
+    pub(crate) fn try_clone(&self) -> Result<Self, io::Error> {
+        use HistoricTopologyLazy::*;
+        match self {
+            Topology(topology) => Ok(Topology(topology.clone())),
+            Lazy(file) => Ok(Lazy(file.try_clone()?)),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
-struct HistoricTopologyDisk {
+pub(crate) struct HistoricTopologyDisk {
     topology: String,
     generation: usize,
+    #[serde(default)]
+    species: usize,
+    #[serde(default)]
+    id: Option<u64>,
 }
 
 impl<F> From<HistoricTopology<F>> for HistoricTopologyDisk
@@ -146,6 +148,8 @@ where
         HistoricTopologyDisk {
             topology: history.topology.to_string(),
             generation: history.generation,
+            species: history.species,
+            id: Some(history.topology.id()),
         }
     }
 }
@@ -155,52 +159,122 @@ where
     F: Float + std::ops::AddAssign + Display + Send,
 {
     fn from(disk: HistoricTopologyDisk) -> HistoricTopology<F> {
+        let mut topology = Topology::from_string(&disk.topology);
+        if let Some(id) = disk.id {
+            topology.set_id(id);
+        }
         HistoricTopology {
-            topology: Topology::from_string(&disk.topology),
+            topology,
             generation: disk.generation,
+            species: disk.species,
         }
     }
 }
 
+/// Summary of a generation returned by `Train::step`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GenerationReport<F> {
+    /// Index of the generation, starting at 0
+    pub generation: usize,
+    /// Best score of the generation
+    pub best_score: F,
+    /// Best score since the start of the training
+    pub best_historical_score: F,
+    /// Number of species after speciation
+    pub species_count: usize,
+    /// Number of networks of the next generation
+    pub population: usize,
+    /// True if every species died, the training can't continue
+    pub extinct: bool,
+}
+
+/// What to do when a network gets a NaN or infinite fitness
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvalidFitnessPolicy {
+    /// Replaces the fitness by the lowest valid fitness of the generation, default
+    #[default]
+    TreatAsMinimum,
+    /// Removes the network from its species so that it doesn't reproduce
+    Drop,
+    /// Runs the generation again up to the given number of times, then treats the remaining
+    /// invalid values as the minimum.
+    ///
+    /// Results given to `step_with_results` can't be evaluated again, they are treated as with
+    /// `TreatAsMinimum`
+    Retry(usize),
+    /// Stops the training with a `TrainingError::InvalidFitness`
+    Error,
+}
+
+/// How a training ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrainingStatus {
+    /// Every iteration was run
+    Completed,
+    /// The training was stopped with a `StopHandle`
+    Cancelled,
+    /// Every species died
+    Extinct,
+}
+
+/// Result of a training that ran until its end
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrainingOutcome {
+    pub status: TrainingStatus,
+    /// Number of generations that were run
+    pub generations: usize,
+}
+
+impl TrainingOutcome {
+    /// Returns true if the training was stopped with a `StopHandle`
+    pub fn is_cancelled(&self) -> bool {
+        self.status == TrainingStatus::Cancelled
+    }
+}
+
 /// The train struct is used to train a Neural Network on a simulation with the NEAT algorithm
-pub struct Train<'a, T, F>
+///
+/// The simulation is either borrowed, see `Train::new`, or owned, see `Train::new_owned`
+pub struct Train<'a, T, F, S = &'a mut T>
 where
     F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
-    T: Game<F>,
-    &'a [F]: rayon::iter::IntoParallelIterator,
+    T: TryGame<F>,
+    S: BorrowMut<T>,
 {
-    pub simulation: &'a mut T,
-    iterations_: usize,
-    max_individuals_: usize,
-    max_layers_: usize,
-    max_per_layers_: usize,
-    delta_threshold_: F,
-    c1_: F,
-    c2_: F,
-    c3_: F,
-    crossovers_: bool,
-    inputs_: Option<usize>,
-    outputs_: Option<usize>,
+    pub simulation: S,
+    config_: TrainConfig<F>,
+    compatibility_metric_: Option<Box<dyn CompatibilityMetric<F>>>,
     topologies_: Vec<TopologySmrtPtr<F>>,
     species_: Vec<Mutex<Species<F>>>,
-    history_: Vec<HistoricTopologyLazy<F>>,
+    history_sink_: Box<dyn HistorySink<F> + 'a>,
+    custom_history_sink_: bool,
     ev_number_: Arc<EvNumber>,
-    save_history_to_disk_: bool,
     best_historical_score_: F,
+    last_best_score_: F,
     no_progress_counter_: usize,
-    proba_: MutationProbabilities,
-    access_train_object_fn_: Option<TrainAccessCallback<'a, T, F>>,
+    novelty_archive_: NoveltyArchive<F>,
+    pareto_front_: Vec<ParetoMember<F>>,
+    generation_: usize,
+    stop_handle_: StopHandle,
+    lineage_: LineageLog,
+    hall_of_fame_: Vec<HallOfFameMember<F>>,
+    evaluation_counts_: Vec<usize>,
+    evaluation_: usize,
+    objectives_: Vec<F>,
+    access_train_object_fn_: Option<TrainAccessCallback<'a, T, F, S>>,
 }
 
-pub type TrainCallback<'a, T, F> = Box<dyn FnMut(&mut Train<'a, T, F>)>;
+pub type TrainCallback<'a, T, F, S = &'a mut T> = Box<dyn FnMut(&mut Train<'a, T, F, S>) + Send>;
+
+/// A `Train` object owning its simulation, it can be stored, moved across threads and returned
+pub type OwnedTrain<T, F> = Train<'static, T, F, T>;
 
 impl<'a, T, F> Train<'a, T, F>
 where
-    T: Game<F>,
+    T: TryGame<F>,
     F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
-    &'a [F]: rayon::iter::IntoParallelIterator,
 {
-    /// Creates a Train<T: Game> instance
+    /// Creates a Train<T: Game> instance borrowing the simulation
     ///
     /// Default values are:
     /// - iterations -> the number of generations to be run: 1000
@@ -255,39 +329,116 @@ where
     /// ```
     #[inline]
     pub fn new(simulation: &'a mut T) -> Train<'a, T, F> {
-        let iterations_: usize = 1000;
-        let max_individuals_: usize = 100;
-        let inputs_ = None;
-        let outputs_ = None;
+        Self::with_simulation(simulation)
+    }
 
+    /// Creates a Train<T: Game> instance borrowing the simulation, with hyperparameters coming
+    /// from a config, e.g. loaded with `TrainConfig::load`
+    ///
+    /// # Arguments
+    ///
+    /// `config` - The hyperparameters
+    ///
+    /// `simulation` - The simulation
+    pub fn from_config(config: &TrainConfig<F>, simulation: &'a mut T) -> Train<'a, T, F> {
+        let mut train = Self::with_simulation(simulation);
+        train.use_config(config.clone());
+        train
+    }
+}
+
+impl<T, F> Train<'static, T, F, T>
+where
+    T: TryGame<F>,
+    F: 'static + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+{
+    /// Creates a Train<T: Game> instance owning the simulation
+    ///
+    /// Unlike `Train::new`, the returned object can be stored in a struct, moved across threads
+    /// and returned, the simulation is given back by `into_simulation`
+    ///
+    /// Default values are the same as `Train::new`
+    #[inline]
+    pub fn new_owned(simulation: T) -> OwnedTrain<T, F> {
+        Self::with_simulation(simulation)
+    }
+}
+
+impl<'a, T, F, S> Train<'a, T, F, S>
+where
+    T: TryGame<F>,
+    F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    S: BorrowMut<T>,
+{
+    fn with_simulation(simulation: S) -> Train<'a, T, F, S> {
         Train {
             simulation,
-            iterations_,
-            max_individuals_,
-            max_layers_: 4,
-            max_per_layers_: 20,
-            delta_threshold_: F::from(3).unwrap(),
-            c1_: F::one(),
-            c2_: F::one(),
-            c3_: F::one(),
-            crossovers_: true,
-            inputs_,
-            outputs_,
+            config_: TrainConfig::default(),
+            compatibility_metric_: None,
             topologies_: Vec::new(),
             species_: Vec::new(),
-            history_: Vec::new(),
+            history_sink_: Box::new(MemoryHistory::new()),
+            custom_history_sink_: false,
             ev_number_: Arc::new(EvNumber::new()),
             best_historical_score_: F::zero(),
+            last_best_score_: F::zero(),
             no_progress_counter_: 0,
             access_train_object_fn_: None,
-            proba_: MutationProbabilities {
-                change_weights: 0.95,
-                guaranteed_new_neuron: 0.2,
-            },
-            save_history_to_disk_: false,
+            novelty_archive_: NoveltyArchive::new(),
+            pareto_front_: Vec::new(),
+            generation_: 0,
+            stop_handle_: StopHandle::new(),
+            lineage_: LineageLog::new(),
+            hall_of_fame_: Vec::new(),
+            evaluation_counts_: Vec::new(),
+            evaluation_: 0,
+            objectives_: Vec::new(),
         }
     }
 
+    /// Returns the hyperparameters set so far
+    #[inline]
+    pub fn config(&self) -> &TrainConfig<F> {
+        &self.config_
+    }
+
+    /// Replaces all the hyperparameters
+    ///
+    /// # Arguments
+    ///
+    /// `config` - The new hyperparameters
+    #[inline]
+    pub fn use_config(&mut self, config: TrainConfig<F>) -> &mut Self {
+        self.config_ = config;
+        self
+    }
+
+    /// Returns the directory where the history and the config are written when
+    /// `save_history_to_disk` is enabled or the history sink writes to a directory. A temporary
+    /// directory is deleted with the train object
+    #[inline]
+    pub fn history_dir(&self) -> Option<&std::path::Path> {
+        self.history_sink_.directory()
+    }
+
+    /// Sets where the best topology of every species of every generation is stored until
+    /// `post_training`, overriding `save_history_to_disk`
+    ///
+    /// # Arguments
+    ///
+    /// `sink` - The history sink, e.g. `KeepLast::new(DirectoryHistory::new("history")?, 10)`
+    pub fn history_sink<H: HistorySink<F> + 'a>(&mut self, sink: H) -> &mut Self {
+        self.history_sink_ = Box::new(sink);
+        self.custom_history_sink_ = true;
+        self
+    }
+
+    /// Returns the simulation, consuming the train object
+    #[inline]
+    pub fn into_simulation(self) -> S {
+        self.simulation
+    }
+
     /// Sets the number of iterations
     ///
     /// Iterations is the maximum number of generations to be run, optional and defaults to 1000
@@ -296,7 +447,7 @@ where
     ///
     /// `it` - The number of generations to be run
     pub fn iterations(&mut self, it: usize) -> &mut Self {
-        self.iterations_ = it;
+        self.config_.iterations = it;
         self
     }
 
@@ -309,7 +460,7 @@ where
     /// `network_number` - The number of networks per generation
     #[inline]
     pub fn max_individuals(&mut self, network_number: usize) -> &mut Self {
-        self.max_individuals_ = network_number;
+        self.config_.max_individuals = network_number;
         self
     }
 
@@ -322,12 +473,14 @@ where
     /// `threshold` - The new delta threshold
     #[inline]
     pub fn delta_threshold(&mut self, threshold: F) -> &mut Self {
-        self.delta_threshold_ = threshold;
+        self.config_.delta_threshold = threshold;
         self
     }
 
     /// Sets the delta threshold formula parameter  
     ///
+    /// Only used by the default compatibility metric, see `compatibility_metric`
+    ///
     /// The formula is:  
     ///
     /// `delta = (c1 * disjoints + c2 * excess) / (larger_topology_length - initial_size) + (mean(weight_distances) * c3)`
@@ -339,9 +492,35 @@ where
     /// `c3` - Defaults to 1
     #[inline]
     pub fn formula(&mut self, c1: F, c2: F, c3: F) -> &mut Self {
-        self.c1_ = c1;
-        self.c2_ = c2;
-        self.c3_ = c3;
+        self.config_.c1 = c1;
+        self.config_.c2 = c2;
+        self.config_.c3 = c3;
+        self
+    }
+
+    /// Sets the genomic distance used to speciate the topologies
+    ///
+    /// This function is optional as the distance defaults to the NEAT formula, see `formula`
+    ///
+    /// # Arguments
+    ///
+    /// `metric` - The compatibility metric, overrides the parameters set with `formula`
+    #[inline]
+    pub fn compatibility_metric(&mut self, metric: Box<dyn CompatibilityMetric<F>>) -> &mut Self {
+        self.compatibility_metric_ = Some(metric);
+        self
+    }
+
+    /// Sets which topology of a species new topologies are compared to
+    ///
+    /// This function is optional as the representative defaults to the best topology of the species
+    ///
+    /// # Arguments
+    ///
+    /// `representative` - How the representative is chosen
+    #[inline]
+    pub fn species_representative(&mut self, representative: SpeciesRepresentative) -> &mut Self {
+        self.config_.species_representative = representative;
         self
     }
 
@@ -352,7 +531,7 @@ where
     /// `proba` - The new probabilities
     #[inline]
     pub fn mutation_probabilities(&mut self, proba: MutationProbabilities) -> &mut Self {
-        self.proba_ = proba;
+        self.config_.mutation_probabilities = proba;
         self
     }
 
@@ -365,7 +544,7 @@ where
     /// `i` - The number of neurons on the first layer
     #[inline]
     pub fn inputs(&mut self, i: usize) -> &mut Self {
-        self.inputs_ = Some(i);
+        self.config_.inputs = Some(i);
         self
     }
 
@@ -378,7 +557,7 @@ where
     /// `o` - The number of neurons on the last layer
     #[inline]
     pub fn outputs(&mut self, o: usize) -> &mut Self {
-        self.outputs_ = Some(o);
+        self.config_.outputs = Some(o);
         self
     }
 
@@ -391,7 +570,7 @@ where
     /// `layers` - The maximum number of layers
     #[inline]
     pub fn max_layers(&mut self, layers: usize) -> &mut Self {
-        self.max_layers_ = layers;
+        self.config_.max_layers = layers;
         self
     }
 
@@ -404,7 +583,7 @@ where
     /// `n` - The maximum number of neurons per layers
     #[inline]
     pub fn max_per_layers(&mut self, n: usize) -> &mut Self {
-        self.max_per_layers_ = n;
+        self.config_.max_per_layers = n;
         self
     }
 
@@ -417,10 +596,152 @@ where
     /// `should_run` - Whether crossover should be run or not
     #[inline]
     pub fn crossovers(&mut self, should_run: bool) -> &mut Self {
-        self.crossovers_ = should_run;
+        self.config_.crossovers = should_run;
+        self
+    }
+
+    /// Enables novelty search
+    ///
+    /// The game has to implement `behaviour_descriptors`, the selection then uses a blend of the
+    /// fitness and of the novelty of each network. The best scores, the history and the hall of
+    /// fame keep the raw fitness
+    ///
+    /// # Arguments
+    ///
+    /// `novelty` - The novelty search parameters
+    #[inline]
+    pub fn novelty_search(&mut self, novelty: NoveltySearch<F>) -> &mut Self {
+        self.config_.novelty_search = Some(novelty);
+        self
+    }
+
+    /// Returns the behaviours archived by novelty search so far
+    ///
+    /// The archive is also written to `novelty-archive.json` in `history_dir` after every
+    /// generation
+    #[inline]
+    pub fn novelty_archive(&self) -> &NoveltyArchive<F> {
+        &self.novelty_archive_
+    }
+
+    /// Replaces the novelty archive, e.g. to carry on a previous training
+    ///
+    /// # Arguments
+    ///
+    /// `archive` - The archive, e.g. read with `NoveltyArchive::load`
+    #[inline]
+    pub fn restore_novelty_archive(&mut self, archive: NoveltyArchive<F>) -> &mut Self {
+        self.novelty_archive_ = archive;
+        self
+    }
+
+    /// Adds the complexity of the networks as an objective of a multi-objective training
+    ///
+    /// The complexity is the number of enabled genes, smaller networks being preferred
+    ///
+    /// # Arguments
+    ///
+    /// `enabled` - Whether the complexity objective is added, defaults to false
+    #[inline]
+    pub fn complexity_objective(&mut self, enabled: bool) -> &mut Self {
+        self.config_.complexity_objective = enabled;
+        self
+    }
+
+    /// Records every genome, its parents and its mutations in the lineage log, see `lineage`
+    ///
+    /// # Arguments
+    ///
+    /// `enabled` - Whether the lineage is tracked, defaults to false
+    #[inline]
+    pub fn track_lineage(&mut self, enabled: bool) -> &mut Self {
+        self.config_.track_lineage = enabled;
+        self
+    }
+
+    /// Returns the genomes evaluated since `init` when `track_lineage` is enabled
+    ///
+    /// The ancestry of the best topology is given by `lineage().ancestry(best.id())`
+    #[inline]
+    pub fn lineage(&self) -> &LineageLog {
+        &self.lineage_
+    }
+
+    /// Keeps the best genomes ever seen, they are given to `reset_hall_of_fame` before every
+    /// generation and to `post_training_hall_of_fame` at the end of the training
+    ///
+    /// # Arguments
+    ///
+    /// `hall_of_fame` - The size of the hall of fame and the number of its genomes added back to
+    /// every generation
+    #[inline]
+    pub fn hall_of_fame(&mut self, hall_of_fame: HallOfFame) -> &mut Self {
+        self.config_.hall_of_fame = Some(hall_of_fame);
+        self
+    }
+
+    /// Averages the fitness of every genome over all its evaluations and evaluates the best
+    /// genomes of every generation several times, for games whose fitness is noisy
+    ///
+    /// The re-evaluations run `Game::run_generation` with only the re-evaluated networks, they
+    /// are skipped by `step_with_results` and the async training
+    ///
+    /// # Arguments
+    ///
+    /// `noise_handling` - The number of re-evaluations and of re-evaluated genomes
+    #[inline]
+    pub fn noise_handling(&mut self, noise_handling: NoiseHandling) -> &mut Self {
+        self.config_.noise_handling = Some(noise_handling);
+        self
+    }
+
+    /// Tunes the weights of the best genomes of every species between two generations, an
+    /// improved genome replaces the tuned one with a new id, see `MutationOperation::LocalSearch`
+    ///
+    /// The perturbed networks are evaluated with `Game::run_generation`, the local search is
+    /// skipped by `step_with_results` and the async training
+    ///
+    /// # Arguments
+    ///
+    /// `local_search` - The method, the tuned genomes, the number of steps and the budget
+    #[inline]
+    pub fn local_search(&mut self, local_search: LocalSearch) -> &mut Self {
+        self.config_.local_search = Some(local_search);
+        self
+    }
+
+    /// Returns the best genomes seen since `init`, the best first
+    #[inline]
+    pub fn hall_of_fame_members(&self) -> &[HallOfFameMember<F>] {
+        &self.hall_of_fame_
+    }
+
+    /// Returns the Pareto front of the last generation of a multi-objective training
+    #[inline]
+    pub fn pareto_front(&self) -> &[ParetoMember<F>] {
+        &self.pareto_front_
+    }
+
+    /// Sets what to do when a network gets a NaN or infinite fitness
+    ///
+    /// # Arguments
+    ///
+    /// `policy` - The policy, defaults to TreatAsMinimum
+    #[inline]
+    pub fn invalid_fitness_policy(&mut self, policy: InvalidFitnessPolicy) -> &mut Self {
+        self.config_.invalid_fitness_policy = policy;
         self
     }
 
+    /// Returns a handle that can stop the training from another thread
+    ///
+    /// The training stops after the current generation and `post_training` is still called with
+    /// the history so far. The stop request is cleared by `init`, i.e. when a training starts
+    #[inline]
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop_handle_.clone()
+    }
+
     /// Returns the number of species
     #[inline]
     pub fn species_count(&self) -> usize {
@@ -433,29 +754,88 @@ where
     ///
     /// `callback` - Callback called after `reset_players`
     #[inline]
-    pub fn access_train_object(&mut self, callback: TrainCallback<'a, T, F>) -> &mut Self {
+    pub fn access_train_object(&mut self, callback: TrainCallback<'a, T, F, S>) -> &mut Self {
         self.access_train_object_fn_ = Some(callback);
         self
     }
 
-    fn run_iterations(&mut self, tempdir: &Option<TempDir>) -> Result<(), TrainingError> {
-        for i in 0..self.iterations_ {
-            section!();
-            log::info!("Generation {}", i);
-            let now = Instant::now();
-            let results = self.simulation.run_generation();
-            log::info!("RUN GENERATION: {}ms", now.elapsed().as_millis());
-            self.set_last_results(results);
-            let now = Instant::now();
-            self.natural_selection();
-            self.push_to_history(i, tempdir)?;
-            self.reset_species();
-            if self.species_.is_empty() {
-                break;
-            }
+    /// Removes the callback given to `access_train_object`
+    pub(crate) fn take_access_train_object(&mut self) -> Option<TrainAccessCallback<'a, T, F, S>> {
+        self.access_train_object_fn_.take()
+    }
+
+    /// Runs exactly one generation: evaluates the networks with `run_generation`, runs the natural
+    /// selection, speciates and gives the new networks to `reset_players`
+    ///
+    /// Calls `init` if the training wasn't initialized yet
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub fn step(&mut self) -> Result<GenerationReport<F>, TrainingError> {
+        self.step_with(Self::evaluate_generation)
+    }
+
+    /// Same as `step` but uses results computed outside of the game, e.g. asynchronously
+    ///
+    /// # Arguments
+    ///
+    /// `results` - The fitness of every network given to the last `reset_players`, in the same order
+    pub fn step_with_results(
+        &mut self,
+        results: Vec<F>,
+    ) -> Result<GenerationReport<F>, TrainingError> {
+        self.begin_step()?;
+        self.check_results_len(results.len())?;
+        // The results can't be evaluated again, `InvalidFitnessPolicy::Retry` doesn't apply
+        self.end_step(results)
+    }
+
+    pub(crate) fn step_with<E>(
+        &mut self,
+        mut evaluate: E,
+    ) -> Result<GenerationReport<F>, TrainingError>
+    where
+        E: FnMut(&mut Self) -> Result<Vec<F>, TrainingError>,
+    {
+        let now = self.begin_step()?;
+        let mut results = evaluate(self)?;
+        self.check_results_len(results.len())?;
+        let mut attempt = 0;
+        while self.should_retry(attempt, &results) {
+            attempt += 1;
+            log::warn!("INVALID FITNESS, RETRY {}", attempt);
+            self.reset_players()?;
+            results = evaluate(self)?;
+            self.check_results_len(results.len())?;
+        }
+        log::info!("RUN GENERATION: {}ms", now.elapsed().as_millis());
+        self.end_step(results)
+    }
+
+    /// Initializes the training if needed, returns the start time of the generation
+    fn begin_step(&mut self) -> Result<Instant, TrainingError> {
+        if self.species_.is_empty() && self.generation_ == 0 {
+            self.init()?;
+        }
+        section!();
+        log::info!("Generation {}", self.generation_);
+        Ok(Instant::now())
+    }
+
+    /// Runs the selection and the speciation once the networks are evaluated
+    fn end_step(&mut self, results: Vec<F>) -> Result<GenerationReport<F>, TrainingError> {
+        let generation = self.generation_;
+        let best = self.set_last_results(results)?;
+        let now = Instant::now();
+        self.natural_selection();
+        self.push_to_history(generation, best)?;
+        self.reset_species();
+        self.generation_ += 1;
+        let extinct = self.species_.is_empty();
+        if !extinct {
             log::info!("NATURAL SELECTION: {}ms", now.elapsed().as_millis());
             let now = Instant::now();
-            self.reset_players();
+            self.reinject_hall_of_fame();
+            self.reset_players()?;
             log::info!("RESET PLAYERS: {}ms", now.elapsed().as_millis());
             let mut cb_option = self.access_train_object_fn_.take();
             let cb_option_borrow = &mut cb_option;
@@ -464,12 +844,18 @@ where
                 self.access_train_object_fn_ = cb_option;
             }
         }
-
-        Ok(())
+        Ok(GenerationReport {
+            generation,
+            best_score: self.last_best_score_,
+            best_historical_score: self.best_historical_score_,
+            species_count: self.species_.len(),
+            population: if extinct { 0 } else { self.topologies_.len() },
+            extinct,
+        })
     }
 
     /// If set to true, saves the history in the disk instead of keeping in RAM to prevent memory leak.
-    /// The files are saved in a TempFile
+    /// The files are saved in a temporary directory, see `history_sink` to choose the directory
     ///
     /// Defaults to false
     ///
@@ -478,74 +864,281 @@ where
     /// `should_use_disk` - Whether to save history on disk
     #[inline]
     pub fn save_history_to_disk(&mut self, should_use_disk: bool) -> &mut Self {
-        self.save_history_to_disk_ = should_use_disk;
+        self.config_.save_history_to_disk = should_use_disk;
         self
     }
 
-    fn create_temp_dir() -> Result<TempDir, io::Error> {
-        let tmp_dir = TempDir::new(TEMP_DIR)?;
-        Ok(tmp_dir)
+    /// Starts the training.
+    ///
+    /// Returns whether all iterations were run or the training was stopped early
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    #[inline]
+    pub fn start(&mut self) -> Result<TrainingOutcome, TrainingError> {
+        self.start_with(Self::evaluate_generation)
     }
 
-    fn load_post_training_from_temp(
-        tempdir: &TempDir,
-    ) -> Result<Vec<HistoricTopologyLazy<F>>, io::Error> {
-        let mut saved_topologies: Vec<HistoricTopologyLazy<F>> = Vec::new();
-        let path = tempdir.path();
-        let files = std::fs::read_dir(path)?;
-        for file_path in files {
-            let file_path = file_path?;
-            let is_file = file_path.file_type()?.is_file();
-            if is_file {
-                let file = std::fs::File::open(&file_path.path())?;
-                let historic_topology: HistoricTopologyLazy<F> = HistoricTopologyLazy::Lazy(file);
+    pub(crate) fn evaluate_generation(&mut self) -> Result<Vec<F>, TrainingError> {
+        let results = self
+            .simulation
+            .borrow_mut()
+            .try_run_generation()
+            .map_err(TrainingError::game)?;
+        self.check_results_len(results.len())?;
+        let novelties = self.novelty_scores(results.len())?;
+        let results = self.reevaluate_best(results)?;
+        let results = self.run_local_search(results)?;
+        Ok(self.blend_novelty(results, novelties))
+    }
 
-                saved_topologies.push(historic_topology);
+    fn start_with<E>(&mut self, mut evaluate: E) -> Result<TrainingOutcome, TrainingError>
+    where
+        E: FnMut(&mut Self) -> Result<Vec<F>, TrainingError>,
+    {
+        self.init()?;
+        while self.should_continue() {
+            if self.step_with(&mut evaluate)?.extinct {
+                break;
             }
         }
+        self.finish_training()
+    }
 
-        Ok(saved_topologies)
+    /// Returns true if another generation has to be run by `start`
+    pub(crate) fn should_continue(&self) -> bool {
+        self.generation_ < self.config_.iterations && !self.stop_handle_.is_stopped()
     }
 
-    /// Starts the training.
-    ///
-    /// May return a NoInput Error if no input or output is given
-    #[inline]
-    pub fn start(&mut self) -> Result<(), TrainingError> {
-        let topologies_tmp_dir = if self.save_history_to_disk_ {
-            Some(Self::create_temp_dir().map_err(TrainingError::from)?)
+    /// Calls `post_training` at the end of `start` and tells why the training ended
+    pub(crate) fn finish_training(&mut self) -> Result<TrainingOutcome, TrainingError> {
+        let status = if self.species_.is_empty() {
+            TrainingStatus::Extinct
+        } else if self.generation_ < self.config_.iterations {
+            log::info!("TRAINING STOPPED");
+            TrainingStatus::Cancelled
         } else {
-            None
+            TrainingStatus::Completed
         };
-        let inputs = self.inputs_.ok_or(TrainingError::NoInput)?;
+        self.finish()?;
+        Ok(TrainingOutcome {
+            status,
+            generations: self.generation_,
+        })
+    }
 
-        let outputs = self.outputs_.ok_or(TrainingError::NoInput)?;
+    /// Creates the first generation and gives it to `reset_players`, discarding any previous
+    /// population
+    ///
+    /// Only needed when driving the training with `step`, `start` calls it
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub fn init(&mut self) -> Result<(), TrainingError> {
+        self.config_.validate()?;
+        let inputs = self.config_.inputs.unwrap_or_default();
+        let outputs = self.config_.outputs.unwrap_or_default();
+
+        if !self.custom_history_sink_ {
+            self.history_sink_ = if self.config_.save_history_to_disk {
+                Box::new(DirectoryHistory::temporary()?)
+            } else {
+                Box::new(MemoryHistory::new())
+            };
+        }
+        self.history_sink_.clear()?;
+        self.stop_handle_.reset();
+        if let Some(dir) = self.history_sink_.directory() {
+            self.config_.to_f64().save(dir.join(CONFIG_FILE))?;
+        }
+        self.lineage_.clear();
+        self.hall_of_fame_.clear();
+        self.species_.clear();
+        self.generation_ = 0;
+        self.best_historical_score_ = F::zero();
+        self.no_progress_counter_ = 0;
 
         self.species_.push(Mutex::new(Species::new_uniform(
             inputs,
             outputs,
-            self.max_layers_,
-            self.max_per_layers_,
+            self.config_.max_layers,
+            self.config_.max_per_layers,
             &self.ev_number_,
         )));
 
-        self.reset_players();
-        // Run generations
-        self.run_iterations(&topologies_tmp_dir)?;
+        self.reset_players()
+    }
+
+    /// Ends a training driven with `step`, gives the history to `post_training`
+    pub fn finish(&mut self) -> Result<(), TrainingError> {
         section!();
         log::info!("POST TRAINING");
-        if let Some(topologies_tmp_dir) = topologies_tmp_dir {
-            let topologies_on_disk = Self::load_post_training_from_temp(&topologies_tmp_dir)?;
-            for topology in topologies_on_disk {
-                self.history_.push(topology);
-            }
+        let history = self.take_history()?;
+        let hall_of_fame = self.take_hall_of_fame();
+        let simulation = self.simulation.borrow_mut();
+        simulation
+            .try_post_training(history)
+            .map_err(TrainingError::game)?;
+        if let Some(hall_of_fame) = hall_of_fame {
+            simulation
+                .try_post_training_hall_of_fame(hall_of_fame)
+                .map_err(TrainingError::game)?;
         }
-        let mut new_history = Vec::new();
-        std::mem::swap(&mut self.history_, &mut new_history);
-        self.simulation.post_training(new_history);
         Ok(())
     }
 
+    /// Takes the hall of fame out of the train object, None if it isn't enabled
+    pub(crate) fn take_hall_of_fame(&mut self) -> Option<Vec<HallOfFameMember<F>>> {
+        let hall_of_fame = std::mem::take(&mut self.hall_of_fame_);
+        self.config_.hall_of_fame.as_ref().map(|_| hall_of_fame)
+    }
+
+    /// Takes the history out of the history sink, sorted by generation and species
+    pub(crate) fn take_history(&mut self) -> Result<Vec<HistoricTopologyLazy<F>>, TrainingError> {
+        Ok(self
+            .take_history_with_generations()?
+            .into_iter()
+            .map(|(_, historic)| historic)
+            .collect())
+    }
+
+    /// Same as `take_history` with the generation of every topology
+    pub(crate) fn take_history_with_generations(
+        &mut self,
+    ) -> Result<Vec<(usize, HistoricTopologyLazy<F>)>, TrainingError> {
+        self.history_sink_.take_history()
+    }
+
+    /// Returns the number of generations run since `init`
+    #[inline]
+    pub fn generation(&self) -> usize {
+        self.generation_
+    }
+
+    pub(crate) fn iterations_count(&self) -> usize {
+        self.config_.iterations
+    }
+
+    /// Returns the best score of the last generation
+    #[inline]
+    pub fn last_best_score(&self) -> F {
+        self.last_best_score_
+    }
+
+    /// Shares the innovation numbers with other trainings so that their genomes stay compatible
+    pub(crate) fn share_evolution_numbers(&mut self, ev_number: Arc<EvNumber>) {
+        self.ev_number_ = ev_number;
+    }
+
+    /// Returns children of the best topology of the best species, the migrants get new ids and
+    /// keep the fitness of their parent
+    pub(crate) fn emigrants(&self, count: usize) -> Vec<Topology<F>> {
+        self.species_
+            .iter()
+            .map(|spec| {
+                spec.lock()
+                    .unwrap()
+                    .best_topology
+                    .derive(MutationOperation::Migration)
+            })
+            .sorted_by(|top1, top2| {
+                top2.get_last_result()
+                    .partial_cmp(&top1.get_last_result())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .take(count)
+            .collect()
+    }
+
+    /// Adds topologies coming from another population to the current generation, they replace
+    /// the members with the lowest fitness so that the population doesn't exceed
+    /// `max_individuals`
+    pub(crate) fn immigrate(&mut self, topologies: Vec<Topology<F>>) -> Result<(), TrainingError> {
+        if topologies.is_empty() {
+            return Ok(());
+        }
+        let members: Vec<TopologySmrtPtr<F>> = self
+            .species_
+            .iter()
+            .flat_map(|spec| spec.lock().unwrap().topologies.clone())
+            .collect();
+        let excess =
+            (members.len() + topologies.len()).saturating_sub(self.config_.max_individuals);
+        if excess > 0 {
+            let replaced: Vec<TopologySmrtPtr<F>> = members
+                .into_iter()
+                .sorted_by(|top1, top2| {
+                    let result1 = top1.lock().unwrap().get_last_result();
+                    let result2 = top2.lock().unwrap().get_last_result();
+                    result1
+                        .partial_cmp(&result2)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .take(excess)
+                .collect();
+            self.species_.iter_mut().for_each(|spec| {
+                spec.get_mut().unwrap().topologies.retain(|topology| {
+                    !replaced
+                        .iter()
+                        .any(|replaced| Arc::ptr_eq(replaced, topology))
+                });
+            });
+            self.species_
+                .retain(|spec| !spec.lock().unwrap().topologies.is_empty());
+        }
+        self.insert_topologies(topologies);
+        self.reset_players()
+    }
+
+    /// Adds the best genomes of the hall of fame that are not in the population anymore
+    fn reinject_hall_of_fame(&mut self) {
+        let count = match &self.config_.hall_of_fame {
+            Some(hall_of_fame) if hall_of_fame.reinjected > 0 => hall_of_fame.reinjected,
+            _ => return,
+        };
+        let present: HashSet<u64> = self
+            .species_
+            .iter()
+            .flat_map(|spec| {
+                let spec = spec.lock().unwrap();
+                spec.topologies
+                    .iter()
+                    .map(|topology| topology.lock().unwrap().id())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let reinjected: Vec<Topology<F>> = self
+            .hall_of_fame_
+            .iter()
+            .take(count)
+            .filter(|member| !present.contains(&member.topology.id()))
+            .map(|member| member.topology.clone())
+            .collect();
+        if !reinjected.is_empty() {
+            log::info!("REINJECTED FROM HALL OF FAME: {}", reinjected.len());
+            self.insert_topologies(reinjected);
+        }
+    }
+
+    /// Adds topologies to the compatible species, or to new species
+    fn insert_topologies(&mut self, topologies: Vec<Topology<F>>) {
+        let default_metric =
+            NeatCompatibility::new(self.config_.c1, self.config_.c2, self.config_.c3);
+        let metric: &dyn CompatibilityMetric<F> = match &self.compatibility_metric_ {
+            Some(metric) => &**metric,
+            None => &default_metric,
+        };
+        for topology in topologies {
+            let compatible = self.species_.iter().find(|spec| {
+                let spec = spec.lock().unwrap();
+                metric.distance(&topology, &spec.representative) <= self.config_.delta_threshold
+            });
+            let topology_rc = Arc::new(Mutex::new(topology));
+            match compatible {
+                Some(spec) => spec.lock().unwrap().push(topology_rc),
+                None => self.species_.push(Mutex::new(Species::new(topology_rc))),
+            }
+        }
+    }
+
     fn collect_topologies(&mut self) {
         self.topologies_ = cond_iter!(self.species_)
             .map(|mutex| {
@@ -557,33 +1150,427 @@ where
             .collect();
     }
 
-    fn reset_players(&mut self) {
+    fn reset_players(&mut self) -> Result<(), TrainingError> {
         self.collect_topologies();
 
         let networks: Vec<NeuralNetwork<F>> = cond_iter!(self.topologies_)
-            .map(|top_rc| {
-                let lock = top_rc.lock().unwrap();
-                let top = &*lock;
-                unsafe { NeuralNetwork::new(top) }
-            })
+            .map(|top_rc| unsafe { NeuralNetwork::from_shared(top_rc) })
             .collect();
         log::info!(
             "TOPOLOGIES: {}, SPECIES: {}",
             networks.len(),
             self.species_.len()
         );
-        self.simulation.reset_players(networks);
+        let infos = self.network_infos();
+        self.evaluation_ = 0;
+        let simulation = self.simulation.borrow_mut();
+        if self.config_.hall_of_fame.is_some() {
+            simulation
+                .try_reset_hall_of_fame(&self.hall_of_fame_)
+                .map_err(TrainingError::game)?;
+        }
+        simulation
+            .try_reset_players_with_info(infos.into_iter().zip(networks).collect())
+            .map_err(TrainingError::game)
+    }
+
+    /// Identity of the networks, in the same order as `topologies_`
+    ///
+    /// The topologies evaluated for the first time are born in the current generation and
+    /// added to the lineage log
+    fn network_infos(&mut self) -> Vec<NetworkInfo> {
+        let generation = self.generation_;
+        let track_lineage = self.config_.track_lineage;
+        let lineage = &mut self.lineage_;
+        let mut infos = Vec::new();
+        for species in &self.species_ {
+            let species = species.lock().unwrap();
+            for topology in &species.topologies {
+                let mut topology = topology.lock().unwrap();
+                if topology.birth_generation().is_none() {
+                    topology.set_birth_generation(generation);
+                }
+                let info = NetworkInfo {
+                    id: topology.id(),
+                    species_id: species.id(),
+                    birth_generation: topology.birth_generation().unwrap_or_default(),
+                    parents: topology.parents().to_vec(),
+                    genome_size: topology.gene_count(),
+                    generation,
+                    evaluation: 0,
+                };
+                if track_lineage && !lineage.contains(info.id) {
+                    lineage.push(LineageRecord {
+                        id: info.id,
+                        parents: info.parents.clone(),
+                        birth_generation: info.birth_generation,
+                        species_id: info.species_id,
+                        operations: topology.operations().to_vec(),
+                        genome_size: info.genome_size,
+                        fitness: None,
+                    });
+                }
+                infos.push(info);
+            }
+        }
+        infos
+    }
+
+    /// Replaces the fitness by the selection score if novelty search is enabled
+    fn apply_novelty(&mut self, results: Vec<F>) -> Result<Vec<F>, TrainingError> {
+        let novelties = self.novelty_scores(results.len())?;
+        Ok(self.blend_novelty(results, novelties))
+    }
+
+    /// Returns the novelty of every network of the last generation if novelty search is enabled
+    fn novelty_scores(&mut self, count: usize) -> Result<Option<Vec<F>>, TrainingError> {
+        let novelty = match &self.config_.novelty_search {
+            Some(novelty) => novelty,
+            None => return Ok(None),
+        };
+        let behaviours = self
+            .simulation
+            .borrow_mut()
+            .try_behaviour_descriptors()
+            .map_err(TrainingError::game)?;
+        if behaviours.len() != count {
+            return Err(TrainingError::DescriptorLengthMismatch {
+                expected: count,
+                got: behaviours.len(),
+            });
+        }
+        let novelties = self.novelty_archive_.evaluate(novelty, &behaviours);
+        log::info!(
+            "BEST NOVELTY: {} ARCHIVE SIZE: {}",
+            novelties.iter().fold(F::zero(), |acc, &n| acc.max(n)),
+            self.novelty_archive_.len()
+        );
+        Ok(Some(novelties))
     }
 
-    fn set_last_results(&mut self, results: Vec<F>) {
-        cond_iter_mut!(self.topologies_)
-            .zip(cond_iter!(results))
-            .for_each(|(topology, result)| {
-                if result.is_nan() {
-                    panic!("NaN result");
+    /// Returns the selection scores, the raw fitness is kept for the progress and the history
+    fn blend_novelty(&mut self, results: Vec<F>, novelties: Option<Vec<F>>) -> Vec<F> {
+        match (&self.config_.novelty_search, novelties) {
+            (Some(novelty), Some(novelties)) => {
+                let blended = results
+                    .iter()
+                    .zip(novelties)
+                    .map(|(&fitness, novelty_score)| novelty.blend(fitness, novelty_score))
+                    .collect();
+                self.objectives_ = results;
+                blended
+            }
+            _ => results,
+        }
+    }
+
+    /// Evaluates the elites and the best networks of the generation again if noise handling is
+    /// enabled, their results become the mean of all their evaluations of the generation
+    fn reevaluate_best(&mut self, mut results: Vec<F>) -> Result<Vec<F>, TrainingError> {
+        self.evaluation_counts_.clear();
+        let noise = match &self.config_.noise_handling {
+            Some(noise) if noise.reevaluations > 0 => noise.clone(),
+            _ => return Ok(results),
+        };
+        let mut indices: Vec<usize> = results
+            .iter()
+            .positions(|result| result.is_finite())
+            .collect();
+        indices.sort_by(|&a, &b| {
+            results[b]
+                .partial_cmp(&results[a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        indices.truncate(noise.candidates);
+        for (index, topology) in self.topologies_.iter().enumerate() {
+            if topology.lock().unwrap().evaluations() > 0 && !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        if indices.is_empty() {
+            return Ok(results);
+        }
+        let infos = self.network_infos();
+        let topologies: Vec<TopologySmrtPtr<F>> = indices
+            .iter()
+            .map(|&index| self.topologies_[index].clone())
+            .collect();
+        let mut sums: Vec<F> = indices.iter().map(|&index| results[index]).collect();
+        for _ in 0..noise.reevaluations {
+            let players = indices.iter().map(|&index| infos[index].clone()).collect();
+            let reevaluated = self.evaluate_subset(&topologies, players)?;
+            for (sum, result) in sums.iter_mut().zip(reevaluated) {
+                *sum += result;
+            }
+        }
+        let count = noise.reevaluations + 1;
+        self.evaluation_counts_ = vec![1; results.len()];
+        for (&index, sum) in indices.iter().zip(sums) {
+            results[index] = sum / F::from(count).unwrap();
+            self.evaluation_counts_[index] = count;
+        }
+        log::info!("REEVALUATED: {} networks", indices.len());
+        Ok(results)
+    }
+
+    /// Tunes the weights of the best genomes of every species if local search is enabled,
+    /// returns the results with the fitness of the improved genomes
+    fn run_local_search(&mut self, mut results: Vec<F>) -> Result<Vec<F>, TrainingError> {
+        let local_search = match &self.config_.local_search {
+            Some(local_search) if local_search.steps > 0 && local_search.budget > 0 => {
+                local_search.clone()
+            }
+            _ => return Ok(results),
+        };
+        // The topologies are collected species by species, see `collect_topologies`
+        let mut indices = Vec::new();
+        let mut offset = 0;
+        for species in &self.species_ {
+            let len = species.lock().unwrap().topologies.len();
+            let mut members: Vec<usize> = (offset..offset + len)
+                .filter(|&index| results[index].is_finite())
+                .collect();
+            members.sort_by(|&a, &b| {
+                results[b]
+                    .partial_cmp(&results[a])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            indices.extend(members.into_iter().take(local_search.genomes_per_species));
+            offset += len;
+        }
+        indices.sort_by(|&a, &b| {
+            results[b]
+                .partial_cmp(&results[a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let infos = self.network_infos();
+        let generation = self.generation_;
+        let mut step_sizes = vec![local_search.step_size; indices.len()];
+        let mut evaluations = 0;
+        let mut improved = 0;
+        let mut rng = thread_rng();
+        for _ in 0..local_search.steps {
+            let count = indices.len().min(local_search.budget - evaluations);
+            if count == 0 {
+                break;
+            }
+            // Every trial is a new genome, a child of the genome it tunes
+            let trials: Vec<TopologySmrtPtr<F>> = indices[..count]
+                .iter()
+                .zip(&step_sizes)
+                .map(|(&index, &step_size)| {
+                    let mut trial = self.topologies_[index]
+                        .lock()
+                        .unwrap()
+                        .derive(MutationOperation::LocalSearch);
+                    trial.perturb_weights(step_size, &mut rng);
+                    trial.set_birth_generation(generation);
+                    Arc::new(Mutex::new(trial))
+                })
+                .collect();
+            let players = indices[..count]
+                .iter()
+                .zip(&trials)
+                .map(|(&index, trial)| {
+                    let trial = trial.lock().unwrap();
+                    NetworkInfo {
+                        id: trial.id(),
+                        birth_generation: generation,
+                        parents: trial.parents().to_vec(),
+                        ..infos[index].clone()
+                    }
+                })
+                .collect();
+            let trial_results = self.evaluate_subset(&trials, players)?;
+            evaluations += count;
+            let trials = trials.into_iter().zip(trial_results);
+            for (rank, (trial, result)) in trials.enumerate() {
+                let index = indices[rank];
+                let is_better = result > results[index];
+                step_sizes[rank] = local_search
+                    .method
+                    .next_step_size(step_sizes[rank], is_better);
+                if is_better {
+                    let trial = trial.lock().unwrap().clone();
+                    let mut topology = self.topologies_[index].lock().unwrap();
+                    if self.config_.track_lineage {
+                        self.lineage_.set_fitness(
+                            topology.id(),
+                            results[index].to_f64().unwrap_or(f64::NAN),
+                        );
+                        self.lineage_.push(LineageRecord {
+                            id: trial.id(),
+                            parents: trial.parents().to_vec(),
+                            birth_generation: generation,
+                            species_id: infos[index].species_id,
+                            operations: trial.operations().to_vec(),
+                            genome_size: trial.gene_count(),
+                            fitness: None,
+                        });
+                    }
+                    // The trial was evaluated once, see `reevaluate_best`
+                    if let Some(count) = self.evaluation_counts_.get_mut(index) {
+                        *count = 1;
+                    }
+                    *topology = trial;
+                    results[index] = result;
+                    improved += 1;
                 }
-                topology.lock().unwrap().set_last_result(*result);
+            }
+        }
+        log::info!(
+            "LOCAL SEARCH: {} evaluations, {} improvements",
+            evaluations,
+            improved
+        );
+        Ok(results)
+    }
+
+    /// Evaluates some topologies with the game, out of the generation
+    ///
+    /// Every call is a new evaluation of the generation, see `NetworkInfo::evaluation`
+    fn evaluate_subset(
+        &mut self,
+        topologies: &[TopologySmrtPtr<F>],
+        infos: Vec<NetworkInfo>,
+    ) -> Result<Vec<F>, TrainingError> {
+        self.evaluation_ += 1;
+        let evaluation = self.evaluation_;
+        let players = infos
+            .into_iter()
+            .zip(topologies)
+            .map(|(mut info, topology)| {
+                info.evaluation = evaluation;
+                let network = unsafe { NeuralNetwork::from_shared(topology) };
+                (info, network)
             })
+            .collect();
+        let simulation = self.simulation.borrow_mut();
+        simulation
+            .try_reset_players_with_info(players)
+            .map_err(TrainingError::game)?;
+        let results = simulation
+            .try_run_generation()
+            .map_err(TrainingError::game)?;
+        if results.len() != topologies.len() {
+            return Err(TrainingError::FitnessLengthMismatch {
+                expected: topologies.len(),
+                got: results.len(),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Returns an error if the game didn't return exactly one result per network
+    fn check_results_len(&self, len: usize) -> Result<(), TrainingError> {
+        if len != self.topologies_.len() {
+            return Err(TrainingError::FitnessLengthMismatch {
+                expected: self.topologies_.len(),
+                got: len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns true if the generation should be evaluated again because of invalid results
+    fn should_retry(&self, attempt: usize, results: &[F]) -> bool {
+        match self.config_.invalid_fitness_policy {
+            InvalidFitnessPolicy::Retry(max) => {
+                attempt < max && results.iter().any(|result| !result.is_finite())
+            }
+            _ => false,
+        }
+    }
+
+    /// Gives their results to the topologies, returns the best raw fitness of the generation and
+    /// the number of evaluations behind it
+    fn set_last_results(&mut self, mut results: Vec<F>) -> Result<(F, usize), TrainingError> {
+        // The results are selection scores when the raw fitness was kept aside
+        let mut objectives = Some(std::mem::take(&mut self.objectives_))
+            .filter(|objectives| objectives.len() == results.len());
+        if let Some(hall_of_fame) = &self.config_.hall_of_fame {
+            update_hall_of_fame(
+                &mut self.hall_of_fame_,
+                hall_of_fame.size,
+                &self.topologies_,
+                objectives.as_ref().unwrap_or(&results),
+                self.generation_,
+            );
+        }
+        let invalid: Vec<usize> = results
+            .iter()
+            .positions(|result| !result.is_finite())
+            .collect();
+        if !invalid.is_empty() {
+            log::warn!("INVALID FITNESS: {} networks", invalid.len());
+            if self.config_.invalid_fitness_policy == InvalidFitnessPolicy::Error {
+                let index = invalid[0];
+                return Err(TrainingError::InvalidFitness {
+                    index,
+                    value: results[index].to_f64().unwrap_or(f64::NAN),
+                });
+            }
+            replace_by_minimum(&mut results, &invalid);
+            if let Some(objectives) = &mut objectives {
+                replace_by_minimum(objectives, &invalid);
+            }
+        }
+        let evaluation_counts = std::mem::take(&mut self.evaluation_counts_);
+        if self.config_.noise_handling.is_some() {
+            for (index, (topology, result)) in self.topologies_.iter().zip(&results).enumerate() {
+                let count = evaluation_counts.get(index).copied().unwrap_or(1);
+                topology.lock().unwrap().add_evaluations(*result, count);
+            }
+        } else {
+            cond_iter_mut!(self.topologies_)
+                .zip(cond_iter!(results))
+                .for_each(|(topology, result)| {
+                    topology.lock().unwrap().set_last_result(*result);
+                });
+        }
+        if let Some(objectives) = &objectives {
+            for (topology, objective) in self.topologies_.iter().zip(objectives) {
+                topology.lock().unwrap().set_objective(*objective);
+            }
+        }
+        let objectives = objectives.unwrap_or(results);
+        if self.config_.track_lineage {
+            for (topology, objective) in self.topologies_.iter().zip(objectives.iter()) {
+                let id = topology.lock().unwrap().id();
+                self.lineage_
+                    .set_fitness(id, objective.to_f64().unwrap_or(f64::NAN));
+            }
+        }
+        let best = self
+            .topologies_
+            .iter()
+            .map(|topology| {
+                let topology = topology.lock().unwrap();
+                (topology.objective(), topology.evaluations())
+            })
+            .fold(
+                None,
+                |best: Option<(F, usize)>, (objective, evaluations)| match best {
+                    Some((score, _)) if score >= objective => best,
+                    _ => Some((objective, evaluations)),
+                },
+            )
+            .unwrap_or((F::zero(), 0));
+        if self.config_.invalid_fitness_policy == InvalidFitnessPolicy::Drop && !invalid.is_empty()
+        {
+            let dropped: Vec<TopologySmrtPtr<F>> = invalid
+                .iter()
+                .map(|&index| self.topologies_[index].clone())
+                .collect();
+            self.species_.iter_mut().for_each(|spec| {
+                spec.get_mut().unwrap().topologies.retain(|topology| {
+                    !dropped.iter().any(|dropped| Arc::ptr_eq(dropped, topology))
+                });
+            });
+            self.species_
+                .retain(|spec| !spec.lock().unwrap().topologies.is_empty());
+        }
+        Ok(best)
     }
 
     /// Calculates the mean
@@ -619,10 +1606,15 @@ where
             0 => return,
             1 => {
                 let first_spec = &mut *self.species_[0].lock().unwrap();
-                first_spec.max_topologies = self.max_individuals_;
+                first_spec.max_topologies = self.config_.max_individuals;
                 self.ev_number_.reset();
                 let ev_number = self.ev_number_.clone();
-                first_spec.natural_selection(ev_number, self.proba_.clone(), self.crossovers_);
+                first_spec.natural_selection(
+                    ev_number,
+                    self.config_.mutation_probabilities.clone(),
+                    self.config_.crossovers,
+                    self.config_.species_representative,
+                );
                 return;
             }
             _ => {}
@@ -651,17 +1643,17 @@ where
         let sum: F = cond_iter!(self.species_)
             .map(|spec| spec.lock().unwrap().adjusted_fitness)
             .sum();
-        let multiplier: F = F::from(self.max_individuals_).unwrap() / sum;
+        let multiplier: F = F::from(self.config_.max_individuals).unwrap() / sum;
         let mut assigned_count: usize = 0;
         for spec in self.species_.iter_mut().rev() {
             let spec = &mut *spec.get_mut().unwrap();
-            let to_assign = if assigned_count < self.max_individuals_ {
+            let to_assign = if assigned_count < self.config_.max_individuals {
                 (spec.adjusted_fitness * multiplier)
                     .max(F::zero())
                     .round()
                     .to_usize()
                     .unwrap()
-                    .min(self.max_individuals_ - assigned_count)
+                    .min(self.config_.max_individuals - assigned_count)
             } else {
                 0
             };
@@ -670,13 +1662,15 @@ where
         }
         self.ev_number_.reset();
         let ev_number = self.ev_number_.clone();
-        let proba = self.proba_.clone();
-        let run_crossovers = self.crossovers_;
+        let proba = self.config_.mutation_probabilities.clone();
+        let run_crossovers = self.config_.crossovers;
+        let representative = self.config_.species_representative;
         cond_iter_mut!(self.species_).for_each(|species| {
             species.get_mut().unwrap().natural_selection(
                 ev_number.clone(),
                 proba.clone(),
                 run_crossovers,
+                representative,
             );
         });
 
@@ -703,8 +1697,8 @@ where
     fn push_to_history(
         &mut self,
         generation: usize,
-        tempdir: &Option<TempDir>,
-    ) -> Result<(), io::Error> {
+        (best, evaluations): (F, usize),
+    ) -> Result<(), TrainingError> {
         if self.species_.is_empty() {
             return Ok(());
         }
@@ -713,10 +1707,16 @@ where
                 .unwrap()
                 .score()
                 .partial_cmp(&s2.lock().unwrap().score())
-                .unwrap()
+                .unwrap_or(std::cmp::Ordering::Equal)
         });
 
-        let best = { self.species_.last().unwrap().lock().unwrap().score() };
+        self.last_best_score_ = best;
+        // With noise handling, a best score is only recorded once it was re-evaluated
+        let confirmed = self
+            .config_
+            .noise_handling
+            .as_ref()
+            .is_none_or(|noise| evaluations > noise.reevaluations);
 
         {
             log::info!(
@@ -725,12 +1725,14 @@ where
                 best
             );
         }
-        if best > self.best_historical_score_ {
+        if best > self.best_historical_score_ && confirmed {
             self.best_historical_score_ = best;
             self.no_progress_counter_ = 0;
         } else {
             self.no_progress_counter_ += 1;
-            if self.no_progress_counter_ >= self.iterations_ / 10 && self.iterations_ > 500 {
+            if self.no_progress_counter_ >= self.config_.iterations / 10
+                && self.config_.iterations > 500
+            {
                 log::info!(
                     "=========================RESET TO TWO SPECIES========================="
                 );
@@ -742,25 +1744,24 @@ where
             }
         }
 
-        for (idx, species) in self.species_.iter().enumerate() {
-            let topology_history = HistoricTopologyLazy::Topology(HistoricTopology {
-                topology: species.lock().unwrap().best_topology.clone(),
-                generation,
-            });
-            if let Some(tempdir) = tempdir {
-                let file_path = tempdir
-                    .path()
-                    .join(format!("generation-{}-species-{}.json", generation, idx));
-                let mut tmp_file = File::create(file_path)?;
-                let disk_topology_history: HistoricTopologyDisk =
-                    topology_history.into_historic()?.into();
-                match serde_json::to_string(&disk_topology_history) {
-                    Ok(serialized) => tmp_file.write_all(serialized.as_bytes())?,
-                    Err(e) => log::error!("Failed to serialize with error: {:?}", e),
+        let topologies = self
+            .species_
+            .iter()
+            .enumerate()
+            .map(|(idx, species)| {
+                let mut topology = species.lock().unwrap().best_topology.clone();
+                topology.set_last_result(topology.objective());
+                HistoricTopology {
+                    topology,
+                    generation,
+                    species: idx,
                 }
-            } else {
-                self.history_.push(topology_history);
-            }
+            })
+            .collect();
+        self.history_sink_.record(generation, topologies)?;
+        if let (Some(_), Some(dir)) = (&self.config_.novelty_search, self.history_sink_.directory())
+        {
+            self.novelty_archive_.save(dir.join(ARCHIVE_FILE))?;
         }
         Ok(())
     }
@@ -781,10 +1782,13 @@ where
                     .adjusted_fitness
                     .partial_cmp(&spec2.adjusted_fitness)
                     .unwrap_or_else(|| {
-                        panic!(
-                            "First: {}, second: {}, variance {}",
-                            spec1.adjusted_fitness, spec2.adjusted_fitness, variance
-                        )
+                        log::warn!(
+                            "Incomparable adjusted fitness, first: {}, second: {}, variance {}",
+                            spec1.adjusted_fitness,
+                            spec2.adjusted_fitness,
+                            variance
+                        );
+                        std::cmp::Ordering::Equal
                     })
             }
         });
@@ -792,13 +1796,23 @@ where
 
     fn reset_species(&mut self) {
         self.collect_topologies();
+        let pick_random = self.config_.species_representative == SpeciesRepresentative::Random;
         cond_iter_mut!(self.species_).for_each(|spec| {
-            spec.get_mut().unwrap().topologies.clear();
+            let spec = spec.get_mut().unwrap();
+            if pick_random {
+                spec.pick_random_representative();
+            }
+            spec.topologies.clear();
         });
         let mut species = self.species_.split_off(0);
         let topologies = self.topologies_.clone();
-        let delta_t = self.delta_threshold_;
-        let (c1, c2, c3) = (self.c1_, self.c2_, self.c3_);
+        let delta_t = self.config_.delta_threshold;
+        let default_metric =
+            NeatCompatibility::new(self.config_.c1, self.config_.c2, self.config_.c3);
+        let metric: &dyn CompatibilityMetric<F> = match &self.compatibility_metric_ {
+            Some(metric) => &**metric,
+            None => &default_metric,
+        };
         let mut new_species = cond_iter!(topologies)
             .filter_map(|topology_rc| {
                 let top_cp = topology_rc.clone();
@@ -809,9 +1823,9 @@ where
                     for spec in &species {
                         let top2 = {
                             let spec = &*spec.lock().unwrap();
-                            spec.best_topology.clone()
+                            spec.representative.clone()
                         };
-                        let delta = Topology::delta_compatibility(&*top1, &top2, c1, c2, c3);
+                        let delta = metric.distance(&top1, &top2);
                         if delta <= delta_t {
                             let spec = &mut *spec.lock().unwrap();
                             spec.push(topology_rc.clone());
@@ -844,66 +1858,112 @@ where
     }
 }
 
-impl<'a, T, F> Train<'a, T, F>
+/// Replaces the invalid values by the minimum of the valid ones, zero if none is valid
+fn replace_by_minimum<F: Float>(values: &mut [F], invalid: &[usize]) {
+    let minimum = values
+        .iter()
+        .filter(|value| value.is_finite())
+        .fold(None, |min: Option<F>, &value| {
+            Some(min.map_or(value, |min| min.min(value)))
+        })
+        .unwrap_or_else(F::zero);
+    for &index in invalid {
+        values[index] = minimum;
+    }
+}
+
+impl<'a, T, F, S> Train<'a, T, F, S>
 where
     T: GameAsync<F>,
     F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
-    &'a [F]: rayon::iter::IntoParallelIterator,
+    S: BorrowMut<T>,
 {
-    pub async fn start_async(&mut self) -> Result<(), TrainingError> {
-        let topologies_tmp_dir = if self.save_history_to_disk_ {
-            Some(Self::create_temp_dir().map_err(TrainingError::from)?)
-        } else {
-            None
-        };
-        let inputs = self.inputs_.ok_or(TrainingError::NoInput)?;
-
-        let outputs = self.inputs_.ok_or(TrainingError::NoInput)?;
-
-        self.species_.push(Mutex::new(Species::new_uniform(
-            inputs,
-            outputs,
-            self.max_layers_,
-            self.max_per_layers_,
-            &self.ev_number_,
-        )));
-
-        self.reset_players();
-        for i in 0..self.iterations_ {
-            section!();
-            log::info!("Generation {}", i);
-            let now = Instant::now();
-            let results = self.simulation.run_generation_async().await;
-            log::info!("RUN GENERATION: {}ms", now.elapsed().as_millis());
-            self.set_last_results(results);
-            let now = Instant::now();
-            self.natural_selection();
-            self.push_to_history(i, &topologies_tmp_dir)?;
-            self.reset_species();
-            if self.species_.is_empty() {
+    /// Same as `start` but evaluates the networks with `run_generation_async`
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub async fn start_async(&mut self) -> Result<TrainingOutcome, TrainingError> {
+        self.init()?;
+        while self.should_continue() {
+            if self.step_async().await?.extinct {
                 break;
             }
-            log::info!("NATURAL SELECTION: {}ms", now.elapsed().as_millis());
-            let now = Instant::now();
-            self.reset_players();
-            log::info!("RESET PLAYERS: {}ms", now.elapsed().as_millis());
-            let mut cb_option = self.access_train_object_fn_.take();
-            let cb_option_borrow = &mut cb_option;
-            if let Some(cb) = cb_option_borrow {
-                (*cb)(self);
-                self.access_train_object_fn_ = cb_option;
-            }
         }
-        log::info!("POST TRAINING");
-        if let Some(topologies_tmp_dir) = topologies_tmp_dir {
-            let topologies_on_disk = Self::load_post_training_from_temp(&topologies_tmp_dir)?;
-            for topology in topologies_on_disk {
-                self.history_.push(topology);
+        self.finish_training()
+    }
+
+    /// Same as `step` but evaluates the networks with `run_generation_async`
+    pub async fn step_async(&mut self) -> Result<GenerationReport<F>, TrainingError> {
+        let now = self.begin_step()?;
+        let mut results = self.simulation.borrow_mut().run_generation_async().await;
+        self.check_results_len(results.len())?;
+        let mut attempt = 0;
+        while self.should_retry(attempt, &results) {
+            attempt += 1;
+            log::warn!("INVALID FITNESS, RETRY {}", attempt);
+            self.reset_players()?;
+            results = self.simulation.borrow_mut().run_generation_async().await;
+            self.check_results_len(results.len())?;
+        }
+        let results = self.apply_novelty(results)?;
+        log::info!("RUN GENERATION: {}ms", now.elapsed().as_millis());
+        self.end_step(results)
+    }
+}
+
+impl<'a, T, F, S> Train<'a, T, F, S>
+where
+    T: MultiObjectiveGame<F>,
+    F: 'a + Float + Sum + Display + std::ops::AddAssign + std::ops::SubAssign + Send + Sync,
+    S: BorrowMut<T>,
+{
+    /// Starts a multi-objective training.
+    ///
+    /// The networks are ranked with the NSGA-II non-dominated sorting and crowding distance on the
+    /// objectives returned by `run_generation_objectives`, `post_training_pareto` then receives the
+    /// Pareto front of the last generation. The best scores, the stagnation, the history and the
+    /// hall of fame follow the first objective
+    ///
+    /// May return an InvalidConfiguration Error, see `TrainConfig::validate`
+    pub fn start_multi_objective(&mut self) -> Result<TrainingOutcome, TrainingError> {
+        let outcome = self.start_with(Self::evaluate_objectives)?;
+        let front = std::mem::take(&mut self.pareto_front_);
+        self.simulation.borrow_mut().post_training_pareto(front);
+        Ok(outcome)
+    }
+
+    fn evaluate_objectives(&mut self) -> Result<Vec<F>, TrainingError> {
+        let mut objectives = self.simulation.borrow_mut().run_generation_objectives();
+        self.check_results_len(objectives.len())?;
+        if self.config_.complexity_objective {
+            for (objective, topology) in objectives.iter_mut().zip(self.topologies_.iter()) {
+                let gene_count = topology.lock().unwrap().gene_count();
+                objective.push(-F::from(gene_count).unwrap());
             }
         }
-        let mut new_history = Vec::new();
-        std::mem::swap(&mut self.history_, &mut new_history);
-        self.simulation.post_training(new_history);
-        Ok(())
+        let (fitness, fronts) = pareto_fitness(&objectives);
+        // The rank based fitness is only used for the selection, the progress follows the first
+        // objective
+        self.objectives_ = objectives
+            .iter()
+            .map(|objectives| objectives.first().copied().unwrap_or_else(F::zero))
+            .collect();
+        let generation = self.generation_;
+        self.pareto_front_ = match fronts.first() {
+            Some(front) => front
+                .iter()
+                .map(|&index| ParetoMember {
+                    topology: self.topologies_[index].lock().unwrap().clone(),
+                    objectives: objectives[index].clone(),
+                    generation,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        log::info!(
+            "PARETO FRONTS: {}, FIRST FRONT SIZE: {}",
+            fronts.len(),
+            self.pareto_front_.len()
+        );
+        Ok(fitness)
     }
 }